@@ -0,0 +1,470 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Cursor;
+use std::path::{Component, Path};
+
+use anyhow::{anyhow, bail};
+use async_compression::tokio::bufread::BrotliDecoder;
+use async_compression::tokio::write::BrotliEncoder;
+use async_compression::Level;
+use chrono::{DateTime, Utc};
+use futures_lite::StreamExt;
+use serde::{Deserialize, Serialize};
+use tokio::io::{
+    AsyncBufRead, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader, BufWriter, Chain,
+};
+use tokio::sync::oneshot;
+use tokio_tar::{Archive, Builder, Header};
+use tracing::{debug, error, info, instrument, warn};
+use uuid::Uuid;
+
+use crate::application::backups::encryption::{decrypt_backup, encrypt_backup, ENCRYPTION_MAGIC};
+use crate::application::database::system::copy_export_counting_records;
+use crate::application::database::DatabaseRootAccess;
+use crate::application::DatabaseSystem;
+
+const MANIFEST_ENTRY: &str = "manifest.json";
+const DATABASE_ENTRY: &str = "database.surql";
+const IMAGES_ENTRY: &str = "images";
+
+const ARCHIVE_VERSION: u32 = 1;
+
+/// Describes the content of a backup archive.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BackupManifest {
+    pub version: u32,
+    pub time_created: DateTime<Utc>,
+    /// Paths of all image files contained in the archive, relative to the images folder.
+    pub images: Vec<String>,
+    /// Amount of records per table contained in the database export, missing in older archives.
+    #[serde(default)]
+    pub record_counts: Option<BTreeMap<String, u64>>,
+}
+
+/// Writes a [Brotli](BrotliEncoder) compressed tar archive containing the manifest,
+/// the database export and every image file referenced by the database.
+/// The archive is encrypted if a backup key is given.
+#[instrument(skip_all)]
+pub async fn write_backup_archive(
+    db: &DatabaseRootAccess,
+    images_folder: &Path,
+    key: Option<&[u8; 32]>,
+    target: impl AsyncWrite + Unpin + Send + 'static,
+) -> anyhow::Result<BackupManifest> {
+    let Some(key) = key else {
+        return write_plain_archive(db, images_folder, target).await;
+    };
+
+    let (archive_writer, archive_reader) = tokio::io::duplex(64 * 1024);
+    let (archive_complete, plain_complete) = oneshot::channel();
+    let (manifest, encryption) = tokio::join!(
+        async {
+            // Dropping the sender on failure prevents the encryption from being finalized
+            let manifest = write_plain_archive(db, images_folder, archive_writer).await?;
+            let _ = archive_complete.send(());
+            anyhow::Ok(manifest)
+        },
+        encrypt_backup(key, archive_reader, plain_complete, target)
+    );
+    let manifest = manifest?;
+    encryption?;
+    Ok(manifest)
+}
+
+async fn write_plain_archive(
+    db: &DatabaseRootAccess,
+    images_folder: &Path,
+    target: impl AsyncWrite + Unpin + Send + 'static,
+) -> anyhow::Result<BackupManifest> {
+    info!("writing backup archive");
+
+    // The manifest precedes the export, so the export is spooled to a file while counting records
+    let export_path = images_folder.with_file_name(format!(".export_{}", Uuid::now_v7().simple()));
+    let result = write_spooled_archive(db, images_folder, &export_path, target).await;
+    if export_path.exists()
+        && let Err(err) = tokio::fs::remove_file(&export_path).await
+    {
+        warn!(?err, file = %export_path.display(), "unable to remove spooled export");
+    }
+    result
+}
+
+async fn write_spooled_archive(
+    db: &DatabaseRootAccess,
+    images_folder: &Path,
+    export_path: &Path,
+    target: impl AsyncWrite + Unpin + Send + 'static,
+) -> anyhow::Result<BackupManifest> {
+    let image_paths: Vec<String> = db.query("SELECT VALUE path FROM image;").await?.take(0)?;
+    let mut export_file = BufWriter::new(tokio::fs::File::create(export_path).await?);
+    let record_counts = copy_export_counting_records(db.export().await?, &mut export_file).await?;
+    drop(export_file);
+
+    let mut images = Vec::new();
+    for image_path in image_paths {
+        if images_folder.join(&image_path).is_file() {
+            images.push(image_path);
+        } else {
+            warn!(image = %image_path, "image file is missing and thus not backed up");
+        }
+    }
+    let manifest = BackupManifest {
+        version: ARCHIVE_VERSION,
+        time_created: Utc::now(),
+        images,
+        record_counts: Some(record_counts),
+    };
+
+    let mut archive = Builder::new(BrotliEncoder::with_quality(target, Level::Best));
+    append_bytes(
+        &mut archive,
+        MANIFEST_ENTRY,
+        &serde_json::to_vec(&manifest)?,
+    )
+    .await?;
+    let mut export_file = tokio::fs::File::open(export_path).await?;
+    archive
+        .append_file(DATABASE_ENTRY, &mut export_file)
+        .await?;
+    for image in &manifest.images {
+        let mut image_file = tokio::fs::File::open(images_folder.join(image)).await?;
+        archive
+            .append_file(Path::new(IMAGES_ENTRY).join(image), &mut image_file)
+            .await?;
+    }
+    let mut brotli_encoder = archive.into_inner().await?;
+    brotli_encoder.shutdown().await?;
+
+    debug!(images = manifest.images.len(), "backup archive written");
+    Ok(manifest)
+}
+
+/// Restores the database and image files from a backup written by [write_backup_archive].
+/// Plain [Brotli](BrotliDecoder) compressed database exports are accepted as well.
+///
+/// The whole backup is read, decrypted and staged before anything is applied, so a failed restore
+/// leaves both the database and the images folder untouched.
+#[instrument(skip_all)]
+pub async fn restore_backup_archive(
+    db: &DatabaseRootAccess,
+    images_folder: &Path,
+    key: Option<&[u8; 32]>,
+    source: impl AsyncBufRead + Unpin + Send + 'static,
+) -> anyhow::Result<()> {
+    let staging_folder = images_folder.with_file_name(".restore");
+    prepare_staging_folder(&staging_folder).await?;
+
+    let result = async {
+        let staged = stage_backup(&staging_folder, key, source).await?;
+        apply_staged_backup(db, images_folder, &staging_folder, staged).await
+    }
+    .await;
+
+    remove_staging_folder(&staging_folder).await;
+    result
+}
+
+/// Backup restored into a throwaway in-memory datastore.
+pub(super) struct MemoryRestore {
+    pub db: DatabaseRootAccess,
+    /// Amount of records per table the backup was written with.
+    pub record_counts: BTreeMap<String, u64>,
+}
+
+/// Restores a backup into a throwaway in-memory datastore instead of the live database.
+/// The backup is staged in the given folder, which is removed afterwards.
+///
+/// The record counts are taken from the manifest, plain exports and older archives without
+/// them are counted instead.
+pub(super) async fn restore_backup_in_memory(
+    staging_folder: &Path,
+    key: Option<&[u8; 32]>,
+    source: impl AsyncBufRead + Unpin + Send + 'static,
+) -> anyhow::Result<MemoryRestore> {
+    prepare_staging_folder(staging_folder).await?;
+
+    let result = async {
+        let staged = stage_backup(staging_folder, key, source).await?;
+        let export_path = staging_folder.join(DATABASE_ENTRY);
+        let record_counts = match staged {
+            StagedBackup::Archive {
+                record_counts: Some(record_counts),
+                ..
+            } => record_counts,
+            _ => {
+                let export = BufReader::new(tokio::fs::File::open(&export_path).await?);
+                copy_export_counting_records(export, &mut tokio::io::sink()).await?
+            }
+        };
+        let export = BufReader::new(tokio::fs::File::open(&export_path).await?);
+        let db = DatabaseSystem::in_memory_import(export).await?;
+        anyhow::Ok(MemoryRestore { db, record_counts })
+    }
+    .await;
+
+    remove_staging_folder(staging_folder).await;
+    result
+}
+
+async fn prepare_staging_folder(staging_folder: &Path) -> std::io::Result<()> {
+    if staging_folder.exists() {
+        tokio::fs::remove_dir_all(staging_folder).await?;
+    }
+    tokio::fs::create_dir_all(staging_folder.join(IMAGES_ENTRY)).await
+}
+
+async fn remove_staging_folder(staging_folder: &Path) {
+    if !staging_folder.exists() {
+        return;
+    }
+    if let Err(err) = tokio::fs::remove_dir_all(staging_folder).await {
+        warn!(?err, folder = %staging_folder.display(), "unable to remove staging folder");
+    }
+}
+
+/// Content of a backup which has been fully read and verified, but not yet applied.
+/// The database export resides in the staging folder, images in its images subfolder.
+enum StagedBackup {
+    /// A plain database export without images.
+    Export,
+    /// A backup archive.
+    Archive {
+        images: BTreeSet<String>,
+        record_counts: Option<BTreeMap<String, u64>>,
+    },
+}
+
+async fn stage_backup(
+    staging_folder: &Path,
+    key: Option<&[u8; 32]>,
+    source: impl AsyncBufRead + Unpin + Send + 'static,
+) -> anyhow::Result<StagedBackup> {
+    let (head, source) = sniff(source, ENCRYPTION_MAGIC.len()).await?;
+    if head != ENCRYPTION_MAGIC {
+        return stage_compressed(staging_folder, source).await;
+    }
+
+    let Some(key) = key else {
+        bail!("backup is encrypted but no key is configured in secrets/backup.toml");
+    };
+    info!("decrypting backup");
+    let (plain_writer, plain_reader) = tokio::io::duplex(64 * 1024);
+    let (decryption, staged) = tokio::join!(
+        decrypt_backup(key, source, plain_writer),
+        stage_compressed(staging_folder, BufReader::new(plain_reader))
+    );
+    decryption?;
+    staged
+}
+
+async fn stage_compressed(
+    staging_folder: &Path,
+    source: impl AsyncBufRead + Unpin + Send + 'static,
+) -> anyhow::Result<StagedBackup> {
+    // Tar archives start with the name of their first entry, which is always the manifest
+    let (head, mut content) = sniff(BrotliDecoder::new(source), MANIFEST_ENTRY.len()).await?;
+    if head != MANIFEST_ENTRY.as_bytes() {
+        info!("restoring plain database export");
+        let mut export_file = tokio::fs::File::create(staging_folder.join(DATABASE_ENTRY)).await?;
+        tokio::io::copy(&mut content, &mut export_file).await?;
+        return Ok(StagedBackup::Export);
+    }
+
+    info!("restoring backup archive");
+    let mut manifest = None;
+    let mut has_export = false;
+    let mut staged_images = BTreeSet::new();
+
+    let mut archive = Archive::new(content);
+    let mut entries = archive.entries()?;
+    while let Some(entry) = entries.next().await {
+        let mut entry = entry?;
+        let entry_path = entry.path()?.to_path_buf();
+
+        if entry_path == Path::new(MANIFEST_ENTRY) {
+            let mut bytes = Vec::new();
+            entry.read_to_end(&mut bytes).await?;
+            manifest = Some(serde_json::from_slice::<BackupManifest>(&bytes)?);
+        } else if entry_path == Path::new(DATABASE_ENTRY) {
+            entry.unpack(staging_folder.join(DATABASE_ENTRY)).await?;
+            has_export = true;
+        } else if let Some(image) = image_file_name(&entry_path) {
+            entry
+                .unpack(staging_folder.join(IMAGES_ENTRY).join(&image))
+                .await?;
+            staged_images.insert(image);
+        } else {
+            warn!(entry = %entry_path.display(), "unknown entry in backup archive ignored");
+        }
+    }
+    drop(entries);
+
+    // Consume the remaining padding, so the whole compressed stream is verified
+    let mut remaining = archive
+        .into_inner()
+        .map_err(|_| anyhow!("backup archive is still in use"))?;
+    tokio::io::copy(&mut remaining, &mut tokio::io::sink()).await?;
+
+    let Some(manifest) = manifest else {
+        bail!("backup archive contains no manifest");
+    };
+    if manifest.version > ARCHIVE_VERSION {
+        bail!(
+            "backup archive has version {} but only versions up to {} are supported",
+            manifest.version,
+            ARCHIVE_VERSION
+        );
+    }
+    if !has_export {
+        bail!("backup archive contains no database export");
+    }
+    if let Some(missing) = manifest
+        .images
+        .iter()
+        .find(|image| !staged_images.contains(*image))
+    {
+        bail!("backup archive is missing image file `{missing}`");
+    }
+
+    Ok(StagedBackup::Archive {
+        images: staged_images,
+        record_counts: manifest.record_counts,
+    })
+}
+
+async fn apply_staged_backup(
+    db: &DatabaseRootAccess,
+    images_folder: &Path,
+    staging_folder: &Path,
+    staged: StagedBackup,
+) -> anyhow::Result<()> {
+    let export = BufReader::new(tokio::fs::File::open(staging_folder.join(DATABASE_ENTRY)).await?);
+    let images = match staged {
+        StagedBackup::Export => {
+            db.import(export).await?;
+            info!("restored database from plain export");
+            return Ok(());
+        }
+        StagedBackup::Archive { images, .. } => images,
+    };
+
+    db.import(export).await?;
+    swap_images_folder(images_folder, &staging_folder.join(IMAGES_ENTRY)).await?;
+    info!(
+        images = images.len(),
+        "restored database and images from backup"
+    );
+
+    Ok(())
+}
+
+/// Replaces the images folder with the staging folder. The previous images are only removed once
+/// the staged ones are in place, and are moved back if that fails.
+async fn swap_images_folder(images_folder: &Path, staging_folder: &Path) -> anyhow::Result<()> {
+    let replaced_folder = images_folder.with_file_name(".images_replaced");
+    let had_images = images_folder.exists();
+    if had_images {
+        if replaced_folder.exists() {
+            tokio::fs::remove_dir_all(&replaced_folder).await?;
+        }
+        tokio::fs::rename(images_folder, &replaced_folder)
+            .await
+            .map_err(|err| anyhow!("unable to move previous images aside: {:?}", err))?;
+    }
+
+    if let Err(err) = tokio::fs::rename(staging_folder, images_folder).await {
+        if had_images && let Err(err) = tokio::fs::rename(&replaced_folder, images_folder).await {
+            error!(?err, folder = %replaced_folder.display(), "unable to move previous images back");
+        }
+        bail!("unable to move restored images into place: {:?}", err);
+    }
+
+    if had_images {
+        remove_staging_folder(&replaced_folder).await;
+    }
+    Ok(())
+}
+
+/// Reads up to `length` bytes from the start of `reader`. Returns them together with a reader
+/// yielding the complete content including those bytes.
+async fn sniff<R: AsyncRead + Unpin>(
+    mut reader: R,
+    length: usize,
+) -> std::io::Result<(Vec<u8>, Chain<Cursor<Vec<u8>>, R>)> {
+    let mut head = Vec::new();
+    (&mut reader)
+        .take(length as u64)
+        .read_to_end(&mut head)
+        .await?;
+    Ok((head.clone(), Cursor::new(head).chain(reader)))
+}
+
+/// Extracts the file name of an image entry, rejecting anything outside the images entry.
+fn image_file_name(entry_path: &Path) -> Option<String> {
+    let relative = entry_path.strip_prefix(IMAGES_ENTRY).ok()?;
+    let mut components = relative.components();
+    let (Some(Component::Normal(file_name)), None) = (components.next(), components.next()) else {
+        return None;
+    };
+    file_name.to_str().map(|name| name.to_string())
+}
+
+async fn append_bytes<W: AsyncWrite + Unpin + Send + 'static>(
+    archive: &mut Builder<W>,
+    path: impl AsRef<Path>,
+    bytes: &[u8],
+) -> std::io::Result<()> {
+    let mut header = Header::new_gnu();
+    header.set_size(bytes.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(Utc::now().timestamp() as u64);
+    archive.append_data(&mut header, path, bytes).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    async fn folder_with_file(folder: &Path, file: &str) {
+        tokio::fs::create_dir_all(folder).await.unwrap();
+        tokio::fs::write(folder.join(file), file).await.unwrap();
+    }
+
+    fn test_folder(name: &str) -> PathBuf {
+        let folder =
+            std::env::temp_dir().join(format!("mycolog_{name}_{}", uuid::Uuid::now_v7().simple()));
+        std::fs::create_dir_all(&folder).unwrap();
+        folder
+    }
+
+    #[tokio::test]
+    async fn swap_replaces_images() {
+        let folder = test_folder("swap");
+        let images = folder.join("images");
+        let staging = folder.join(".images_restore");
+        folder_with_file(&images, "old.png").await;
+        folder_with_file(&staging, "new.png").await;
+
+        swap_images_folder(&images, &staging).await.unwrap();
+
+        assert!(images.join("new.png").is_file());
+        assert!(!images.join("old.png").exists());
+        assert!(!staging.exists());
+        assert!(!folder.join(".images_replaced").exists());
+        tokio::fs::remove_dir_all(folder).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn failed_swap_keeps_images() {
+        let folder = test_folder("failed_swap");
+        let images = folder.join("images");
+        folder_with_file(&images, "old.png").await;
+
+        let result = swap_images_folder(&images, &folder.join(".images_restore")).await;
+
+        assert!(result.is_err());
+        assert!(images.join("old.png").is_file());
+        tokio::fs::remove_dir_all(folder).await.unwrap();
+    }
+}
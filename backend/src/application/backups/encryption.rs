@@ -0,0 +1,200 @@
+use anyhow::{anyhow, bail};
+use chacha20poly1305::aead::generic_array::GenericArray;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::stream::{DecryptorBE32, EncryptorBE32};
+use chacha20poly1305::aead::OsRng;
+use chacha20poly1305::XChaCha20Poly1305;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::oneshot;
+use tracing::{debug, instrument};
+
+/// Prefix identifying encrypted backups, followed by the stream nonce and the encrypted chunks.
+pub const ENCRYPTION_MAGIC: &[u8] = b"mycolog-encrypted-backup-v1\n";
+
+/// XChaCha20 uses 24 byte nonces, of which the STREAM construction reserves 5 bytes.
+const NONCE_SIZE: usize = 19;
+const TAG_SIZE: usize = 16;
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Encrypts `plain` into `target` using XChaCha20-Poly1305 in the STREAM construction,
+/// which authenticates every chunk as well as the end of the stream.
+///
+/// The end of the stream is only authenticated once `plain_complete` receives a value. If its
+/// sender is dropped instead, `plain` ended prematurely and the encryption fails, so truncated
+/// content never becomes a complete-looking encrypted backup.
+#[instrument(skip_all)]
+pub async fn encrypt_backup(
+    key: &[u8; 32],
+    mut plain: impl AsyncRead + Unpin,
+    plain_complete: oneshot::Receiver<()>,
+    mut target: impl AsyncWrite + Unpin,
+) -> anyhow::Result<()> {
+    let mut nonce = [0u8; NONCE_SIZE];
+    OsRng.fill_bytes(&mut nonce);
+    let mut encryptor = EncryptorBE32::<XChaCha20Poly1305>::new(
+        GenericArray::from_slice(key),
+        GenericArray::from_slice(&nonce),
+    );
+
+    target.write_all(ENCRYPTION_MAGIC).await?;
+    target.write_all(&nonce).await?;
+
+    let mut chunk = read_chunk(&mut plain, CHUNK_SIZE).await?;
+    loop {
+        let next_chunk = read_chunk(&mut plain, CHUNK_SIZE).await?;
+        if next_chunk.is_empty() {
+            if plain_complete.await.is_err() {
+                bail!("backup ended prematurely, the encrypted backup is not finalized");
+            }
+            let encrypted = encryptor
+                .encrypt_last(chunk.as_slice())
+                .map_err(|_| anyhow!("unable to encrypt last backup chunk"))?;
+            target.write_all(&encrypted).await?;
+            break;
+        }
+
+        let encrypted = encryptor
+            .encrypt_next(chunk.as_slice())
+            .map_err(|_| anyhow!("unable to encrypt backup chunk"))?;
+        target.write_all(&encrypted).await?;
+        chunk = next_chunk;
+    }
+    target.shutdown().await?;
+
+    debug!("backup encrypted");
+    Ok(())
+}
+
+/// Decrypts a backup written by [encrypt_backup] into `target`. Every chunk is authenticated
+/// before it is written, truncated or tampered backups result in an error.
+#[instrument(skip_all)]
+pub async fn decrypt_backup(
+    key: &[u8; 32],
+    mut encrypted: impl AsyncRead + Unpin,
+    mut target: impl AsyncWrite + Unpin,
+) -> anyhow::Result<()> {
+    let mut magic = [0u8; ENCRYPTION_MAGIC.len()];
+    encrypted.read_exact(&mut magic).await?;
+    if magic != ENCRYPTION_MAGIC {
+        bail!("backup is not encrypted");
+    }
+    let mut nonce = [0u8; NONCE_SIZE];
+    encrypted
+        .read_exact(&mut nonce)
+        .await
+        .map_err(|err| anyhow!("encrypted backup is missing its nonce: {:?}", err))?;
+    let mut decryptor = DecryptorBE32::<XChaCha20Poly1305>::new(
+        GenericArray::from_slice(key),
+        GenericArray::from_slice(&nonce),
+    );
+
+    let mut chunk = read_chunk(&mut encrypted, CHUNK_SIZE + TAG_SIZE).await?;
+    loop {
+        let next_chunk = read_chunk(&mut encrypted, CHUNK_SIZE + TAG_SIZE).await?;
+        if next_chunk.is_empty() {
+            let decrypted = decryptor.decrypt_last(chunk.as_slice()).map_err(|_| {
+                anyhow!("backup could not be decrypted, the key is wrong or the backup is damaged")
+            })?;
+            target.write_all(&decrypted).await?;
+            break;
+        }
+
+        let decrypted = decryptor.decrypt_next(chunk.as_slice()).map_err(|_| {
+            anyhow!("backup could not be decrypted, the key is wrong or the backup is damaged")
+        })?;
+        target.write_all(&decrypted).await?;
+        chunk = next_chunk;
+    }
+    target.shutdown().await?;
+
+    debug!("backup decrypted");
+    Ok(())
+}
+
+/// Reads up to `size` bytes, less only if the end of `reader` is reached.
+async fn read_chunk(
+    reader: &mut (impl AsyncRead + Unpin),
+    size: usize,
+) -> std::io::Result<Vec<u8>> {
+    let mut chunk = Vec::with_capacity(size);
+    reader.take(size as u64).read_to_end(&mut chunk).await?;
+    Ok(chunk)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: [u8; 32] = [7; 32];
+
+    async fn encrypt(plain: &[u8]) -> Vec<u8> {
+        let (complete, plain_complete) = oneshot::channel();
+        complete.send(()).unwrap();
+        let mut encrypted = Vec::new();
+        encrypt_backup(&KEY, plain, plain_complete, &mut encrypted)
+            .await
+            .unwrap();
+        encrypted
+    }
+
+    async fn decrypt(key: &[u8; 32], encrypted: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let mut plain = Vec::new();
+        decrypt_backup(key, encrypted, &mut plain).await?;
+        Ok(plain)
+    }
+
+    #[tokio::test]
+    async fn round_trip() {
+        for size in [0, 1, CHUNK_SIZE, 2 * CHUNK_SIZE + 123] {
+            let plain = (0..size).map(|byte| byte as u8).collect::<Vec<_>>();
+            let encrypted = encrypt(&plain).await;
+            assert!(encrypted.starts_with(ENCRYPTION_MAGIC));
+            assert_eq!(
+                decrypt(&KEY, &encrypted).await.unwrap(),
+                plain,
+                "size {size}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_truncated_backup() {
+        let encrypted = encrypt(&vec![1; 2 * CHUNK_SIZE + 123]).await;
+        let header_size = ENCRYPTION_MAGIC.len() + NONCE_SIZE;
+
+        // Cut at a chunk boundary, so only the authenticated end of the stream is missing
+        let truncated = &encrypted[..header_size + 2 * (CHUNK_SIZE + TAG_SIZE)];
+        assert!(decrypt(&KEY, truncated).await.is_err());
+        assert!(decrypt(&KEY, &encrypted[..encrypted.len() - 1])
+            .await
+            .is_err());
+        assert!(decrypt(&KEY, &encrypted[..header_size - 1]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn rejects_tampered_backup_and_wrong_key() {
+        let mut encrypted = encrypt(b"backup").await;
+        assert!(decrypt(&[8; 32], &encrypted).await.is_err());
+
+        let last = encrypted.len() - 1;
+        encrypted[last] ^= 1;
+        assert!(decrypt(&KEY, &encrypted).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn rejects_plain_backup() {
+        assert!(decrypt(&KEY, b"not an encrypted backup at all")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn does_not_finalize_incomplete_plain_backup() {
+        let (complete, plain_complete) = oneshot::channel::<()>();
+        drop(complete);
+        let mut encrypted = Vec::new();
+        let result = encrypt_backup(&KEY, &[1u8; 100][..], plain_complete, &mut encrypted).await;
+        assert!(result.is_err());
+        assert!(decrypt(&KEY, &encrypted).await.is_err());
+    }
+}
@@ -1,51 +1,435 @@
-use std::os::unix::prelude::MetadataExt;
-use std::path::PathBuf;
+use std::collections::BTreeSet;
 
-#[derive(Clone, Debug)]
-pub enum BackupLimit {
+use chrono::{DateTime, Duration, Local, NaiveDateTime, TimeZone};
+
+/// Format of the creation time inside backup file names, e.g. `backup_20240101120000.tar.br`.
+pub const BACKUP_TIME_FORMAT: &str = "%Y%m%d%H%M%S";
+
+/// Combinable retention rules for backups. A backup is deleted as soon as any configured rule
+/// rejects it, the newest backup is always kept.
+#[derive(Clone, Debug, Default)]
+pub struct BackupLimit {
     // There may not be more backups than the specified amount
-    MaxAmountBackups(u64),
+    pub max_amount: Option<u64>,
     // The backup directory may not grow larger than the specified amount in megabytes
-    MaxSumSizeMB(u64),
+    pub max_size_mb: Option<u64>,
     // Oldest backup may not be older than the specified amount in hours
-    MaxAgeHours(u64),
+    pub max_age_hours: Option<u64>,
+    // Keep the newest backup of each of the specified amount of most recent hours, days, weeks and months
+    pub keep_hourly: Option<u64>,
+    pub keep_daily: Option<u64>,
+    pub keep_weekly: Option<u64>,
+    pub keep_monthly: Option<u64>,
 }
 
-impl BackupLimit {
-    pub fn check_exceeded(&self, paths: &[PathBuf]) -> bool {
-        match self {
-            BackupLimit::MaxAmountBackups(amount) => max_amount_backups(amount.clone(), paths),
-            BackupLimit::MaxSumSizeMB(mb) => max_sum_size(mb.clone(), paths),
-            BackupLimit::MaxAgeHours(hours) => max_age(hours.clone(), paths),
-        }
-    }
+/// A backup file with its creation time parsed from the file name.
+#[derive(Clone, Debug)]
+pub struct BackupFile {
+    pub file_name: String,
+    pub time: DateTime<Local>,
+    pub size: u64,
 }
 
-fn max_amount_backups(amount: u64, paths: &[PathBuf]) -> bool {
-    paths.len() as u64 > amount
+impl BackupFile {
+    /// Parses the creation time from the file name, legacy `.br` backups share the same
+    /// naming scheme. Returns [None] if the file is no backup.
+    pub fn parse(file_name: impl Into<String>, size: u64) -> Option<Self> {
+        let file_name = file_name.into();
+        let name = file_name.strip_prefix("backup_")?;
+        let time = name
+            .strip_suffix(".tar.br")
+            .or_else(|| name.strip_suffix(".br"))?;
+        let naive_time = NaiveDateTime::parse_from_str(time, BACKUP_TIME_FORMAT).ok()?;
+        let time = Local.from_local_datetime(&naive_time).earliest()?;
+
+        Some(BackupFile {
+            file_name,
+            time,
+            size,
+        })
+    }
 }
 
-fn max_sum_size(mb: u64, paths: &[PathBuf]) -> bool {
-    let mut sum_size = 0;
-    for path in paths {
-        if let Ok(metadata) = std::fs::metadata(path) {
-            sum_size += metadata.size();
+impl BackupLimit {
+    pub fn is_unlimited(&self) -> bool {
+        self.max_amount.is_none()
+            && self.max_size_mb.is_none()
+            && self.max_age_hours.is_none()
+            && !self.has_rotation()
+    }
+
+    /// Describes settings which contradict each other, e.g. a rotation keeping more backups than
+    /// `max_amount` allows. Each problem is returned with its config key.
+    pub fn conflicts(&self) -> Vec<(&'static str, String)> {
+        let mut conflicts = Vec::new();
+
+        // Every period keeps its own backups, so the rotation keeps up to the sum of all of them
+        let rotation_amount = [
+            self.keep_hourly,
+            self.keep_daily,
+            self.keep_weekly,
+            self.keep_monthly,
+        ]
+        .into_iter()
+        .flatten()
+        .reduce(u64::saturating_add);
+        if let Some(amount) = self.max_amount
+            && let Some(rotation_amount) = rotation_amount
+            && amount < rotation_amount
+        {
+            conflicts.push((
+                "max_amount",
+                format!("{amount} is less than the {rotation_amount} backups kept by rotation"),
+            ));
         }
+
+        let rotation_hours = [
+            self.keep_hourly,
+            self.keep_daily.map(|days| days.saturating_mul(24)),
+            self.keep_weekly.map(|weeks| weeks.saturating_mul(24 * 7)),
+            self.keep_monthly
+                .map(|months| months.saturating_mul(24 * 31)),
+        ]
+        .into_iter()
+        .flatten()
+        .max();
+        if let Some(hours) = self.max_age_hours
+            && let Some(rotation_hours) = rotation_hours
+            && hours < rotation_hours
+        {
+            conflicts.push((
+                "max_age",
+                format!(
+                    "{hours} hours deletes backups the rotation keeps for {rotation_hours} hours"
+                ),
+            ));
+        }
+
+        conflicts
     }
 
-    sum_size / 2u64.pow(20) > mb
-}
+    fn has_rotation(&self) -> bool {
+        self.keep_hourly.is_some()
+            || self.keep_daily.is_some()
+            || self.keep_weekly.is_some()
+            || self.keep_monthly.is_some()
+    }
+
+    /// Selects the backups to delete. `backups` has to be sorted from oldest to newest.
+    pub fn expired<'a>(
+        &self,
+        backups: &'a [BackupFile],
+        now: DateTime<Local>,
+    ) -> Vec<&'a BackupFile> {
+        let Some(newest) = backups.len().checked_sub(1) else {
+            return Vec::new();
+        };
+        let mut expired = BTreeSet::new();
 
-fn max_age(hours: u64, paths: &[PathBuf]) -> bool {
-    for path in paths {
-        if let Ok(metadata) = std::fs::metadata(path) {
-            if let Ok(created) = metadata.created() {
-                if let Ok(elapsed) = created.elapsed() {
-                    return elapsed.as_secs() / 3600 > hours;
+        if let Some(amount) = self.max_amount {
+            let amount = amount.max(1) as usize;
+            expired.extend(0..backups.len().saturating_sub(amount));
+        }
+        if let Some(hours) = self.max_age_hours {
+            let oldest_allowed = now - Duration::hours(hours as i64);
+            expired.extend((0..newest).filter(|&index| backups[index].time < oldest_allowed));
+        }
+        if self.has_rotation() {
+            let kept = self.rotation_kept(backups);
+            expired.extend((0..newest).filter(|index| !kept.contains(index)));
+        }
+        if let Some(mb) = self.max_size_mb {
+            let mut sum_size: u64 = (0..backups.len())
+                .filter(|index| !expired.contains(index))
+                .map(|index| backups[index].size)
+                .sum();
+            for index in 0..newest {
+                if sum_size / 2u64.pow(20) <= mb {
+                    break;
+                }
+                if expired.insert(index) {
+                    sum_size -= backups[index].size;
                 }
-                break;
             }
         }
+
+        expired.remove(&newest);
+        expired.into_iter().map(|index| &backups[index]).collect()
+    }
+
+    /// Grandfather-father-son rotation, returns the indices of all backups to keep.
+    fn rotation_kept(&self, backups: &[BackupFile]) -> BTreeSet<usize> {
+        let mut kept = BTreeSet::new();
+        keep_newest_per_period(backups, self.keep_hourly, "%Y%m%d%H", &mut kept);
+        keep_newest_per_period(backups, self.keep_daily, "%Y%m%d", &mut kept);
+        keep_newest_per_period(backups, self.keep_weekly, "%G%V", &mut kept);
+        keep_newest_per_period(backups, self.keep_monthly, "%Y%m", &mut kept);
+        kept
+    }
+}
+
+/// Keeps the newest backup of each of the `amount` most recent periods, which are identified by
+/// formatting the backup time with `period_format`.
+fn keep_newest_per_period(
+    backups: &[BackupFile],
+    amount: Option<u64>,
+    period_format: &str,
+    kept: &mut BTreeSet<usize>,
+) {
+    let Some(amount) = amount else {
+        return;
+    };
+
+    let mut periods = 0;
+    let mut last_period = None;
+    for (index, backup) in backups.iter().enumerate().rev() {
+        if periods >= amount {
+            break;
+        }
+        let period = backup.time.format(period_format).to_string();
+        if last_period.as_ref() != Some(&period) {
+            kept.insert(index);
+            last_period = Some(period);
+            periods += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: u64 = 2u64.pow(20);
+
+    fn backup(year: i32, month: u32, day: u32, hour: u32, minute: u32, size: u64) -> BackupFile {
+        let time = Local
+            .with_ymd_and_hms(year, month, day, hour, minute, 0)
+            .unwrap();
+        BackupFile {
+            file_name: format!("backup_{}.tar.br", time.format(BACKUP_TIME_FORMAT)),
+            time,
+            size,
+        }
+    }
+
+    fn expired_names(limit: &BackupLimit, backups: &[BackupFile]) -> Vec<String> {
+        let now = backups.last().unwrap().time + Duration::minutes(1);
+        limit
+            .expired(backups, now)
+            .into_iter()
+            .map(|backup| backup.time.format("%Y-%m-%d %H:%M").to_string())
+            .collect()
+    }
+
+    #[test]
+    fn parses_backup_file_names() {
+        let backup = BackupFile::parse("backup_20240101120000.tar.br", 3).unwrap();
+        assert_eq!(
+            backup.time,
+            Local.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap()
+        );
+        assert!(BackupFile::parse("backup_20240101120000.br", 3).is_some());
+        assert!(BackupFile::parse("backup_20240101120000.tar.br.partial", 3).is_none());
+        assert!(BackupFile::parse("notes.txt", 3).is_none());
+    }
+
+    #[test]
+    fn nothing_expires_without_backups() {
+        let limit = BackupLimit {
+            max_amount: Some(1),
+            ..Default::default()
+        };
+        assert!(limit.expired(&[], Local::now()).is_empty());
+    }
+
+    #[test]
+    fn max_amount_keeps_newest() {
+        let backups = [
+            backup(2024, 1, 1, 0, 0, MB),
+            backup(2024, 1, 2, 0, 0, MB),
+            backup(2024, 1, 3, 0, 0, MB),
+        ];
+        let limit = BackupLimit {
+            max_amount: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(expired_names(&limit, &backups), ["2024-01-01 00:00"]);
+    }
+
+    #[test]
+    fn max_age_always_keeps_newest() {
+        let backups = [backup(2024, 1, 1, 0, 0, MB), backup(2024, 1, 2, 0, 0, MB)];
+        let limit = BackupLimit {
+            max_age_hours: Some(1),
+            ..Default::default()
+        };
+        let now = Local.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap();
+        let expired = limit.expired(&backups, now);
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].file_name, backups[0].file_name);
+    }
+
+    #[test]
+    fn daily_rotation_splits_at_midnight() {
+        let backups = [
+            backup(2024, 1, 1, 10, 0, MB),
+            backup(2024, 1, 1, 22, 0, MB),
+            backup(2024, 1, 2, 9, 0, MB),
+            backup(2024, 1, 2, 23, 59, MB),
+            backup(2024, 1, 3, 0, 0, MB),
+        ];
+        let limit = BackupLimit {
+            keep_daily: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(
+            expired_names(&limit, &backups),
+            ["2024-01-01 10:00", "2024-01-01 22:00", "2024-01-02 09:00"]
+        );
+    }
+
+    #[test]
+    fn weekly_rotation_uses_iso_weeks_across_years() {
+        // 2024-12-30 already belongs to the first ISO week of 2025
+        let backups = [
+            backup(2024, 12, 28, 12, 0, MB),
+            backup(2024, 12, 29, 12, 0, MB),
+            backup(2024, 12, 30, 12, 0, MB),
+            backup(2025, 1, 1, 12, 0, MB),
+        ];
+        let limit = BackupLimit {
+            keep_weekly: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(
+            expired_names(&limit, &backups),
+            ["2024-12-28 12:00", "2024-12-30 12:00"]
+        );
+    }
+
+    #[test]
+    fn combined_rotation_keeps_union() {
+        let backups = [
+            backup(2024, 1, 15, 12, 0, MB),
+            backup(2024, 1, 31, 12, 0, MB),
+            backup(2024, 2, 10, 12, 0, MB),
+            backup(2024, 2, 11, 12, 0, MB),
+        ];
+        let limit = BackupLimit {
+            keep_daily: Some(1),
+            keep_monthly: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(
+            expired_names(&limit, &backups),
+            ["2024-01-15 12:00", "2024-02-10 12:00"]
+        );
+    }
+
+    #[test]
+    fn any_rule_rejects() {
+        let backups = [
+            backup(2024, 1, 1, 12, 0, MB),
+            backup(2024, 1, 2, 12, 0, MB),
+            backup(2024, 1, 3, 12, 0, MB),
+        ];
+        let limit = BackupLimit {
+            max_amount: Some(1),
+            keep_daily: Some(3),
+            ..Default::default()
+        };
+        assert_eq!(
+            expired_names(&limit, &backups),
+            ["2024-01-01 12:00", "2024-01-02 12:00"]
+        );
+    }
+
+    #[test]
+    fn size_cap_compares_whole_megabytes() {
+        let backups = [
+            backup(2024, 1, 1, 12, 0, MB),
+            backup(2024, 1, 2, 12, 0, MB),
+            backup(2024, 1, 3, 12, 0, MB / 2),
+        ];
+        // 2.5 MB are rounded down to 2 MB and fit
+        let limit = BackupLimit {
+            max_size_mb: Some(2),
+            ..Default::default()
+        };
+        assert!(expired_names(&limit, &backups).is_empty());
+
+        let limit = BackupLimit {
+            max_size_mb: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(expired_names(&limit, &backups), ["2024-01-01 12:00"]);
+    }
+
+    #[test]
+    fn size_cap_counts_only_remaining_backups() {
+        let backups = [
+            backup(2024, 1, 1, 12, 0, 3 * MB),
+            backup(2024, 1, 2, 12, 0, MB),
+            backup(2024, 1, 3, 12, 0, MB),
+        ];
+        let limit = BackupLimit {
+            max_amount: Some(2),
+            max_size_mb: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(expired_names(&limit, &backups), ["2024-01-01 12:00"]);
+    }
+
+    #[test]
+    fn rotation_amount_is_sum_of_periods() {
+        let limit = BackupLimit {
+            max_amount: Some(7),
+            keep_daily: Some(7),
+            keep_weekly: Some(4),
+            ..Default::default()
+        };
+        assert_eq!(
+            limit.conflicts(),
+            vec![(
+                "max_amount",
+                "7 is less than the 11 backups kept by rotation".to_string()
+            )]
+        );
+
+        let limit = BackupLimit {
+            max_amount: Some(11),
+            ..limit
+        };
+        assert!(limit.conflicts().is_empty());
+    }
+
+    #[test]
+    fn max_age_shorter_than_rotation() {
+        let limit = BackupLimit {
+            max_age_hours: Some(24 * 7),
+            keep_hourly: Some(48),
+            keep_weekly: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(
+            limit.conflicts(),
+            vec![(
+                "max_age",
+                "168 hours deletes backups the rotation keeps for 336 hours".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn huge_rotation_does_not_overflow() {
+        let limit = BackupLimit {
+            max_amount: Some(u64::MAX),
+            max_age_hours: Some(u64::MAX),
+            keep_daily: Some(u64::MAX),
+            keep_weekly: Some(u64::MAX),
+            keep_monthly: Some(u64::MAX),
+            ..Default::default()
+        };
+        assert!(limit.conflicts().is_empty());
     }
-    false
 }
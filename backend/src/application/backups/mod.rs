@@ -5,18 +5,29 @@ use std::sync::Arc;
 use futures_lite::stream::StreamExt;
 use tracing::{error, info, Instrument};
 
+pub use crate::application::backups::archive::{restore_backup_archive, write_backup_archive};
 pub use crate::application::backups::limits::BackupLimit;
 use crate::application::backups::service::backup_service;
+pub use crate::application::backups::targets::{BackupTargetConfig, BackupTargetKind};
+pub use crate::application::backups::verification::{
+    recent_backup_verifications, verify_backup, BackupVerification,
+};
 use crate::context::MycologContext;
 
+mod archive;
+mod encryption;
 mod limits;
 mod service;
+mod targets;
+mod verification;
 
 pub async fn backup_task(context: Arc<MycologContext>) {
     let db = context.db.auth_root();
     let shutdown_token = context.task_cancel_token.clone();
 
-    if let Err(err) = backup_service(&context.config, db, shutdown_token).await {
+    if let Err(err) =
+        backup_service(&context.config, db, &context.secrets.backup, shutdown_token).await
+    {
         error!(?err, "database backup service crashed");
     }
     info!("stopped database backup service");
@@ -1,28 +1,57 @@
-use std::collections::VecDeque;
 use std::fs::File;
 use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::time::Duration;
 
-use anyhow::{anyhow, bail};
+use anyhow::anyhow;
 use chrono::Local;
 use futures_lite::StreamExt;
-use tokio::time::{interval, interval_at, sleep, Instant, MissedTickBehavior};
+use tokio::time::{interval, interval_at, sleep, timeout, Instant, MissedTickBehavior};
 use tokio_util::sync::CancellationToken;
-use tracing::{debug, error, info, instrument, warn};
+use tracing::{debug, error, info, info_span, instrument, warn, Instrument};
 
+use crate::application::backups::limits::BACKUP_TIME_FORMAT;
+use crate::application::backups::targets::{
+    constraint_backups, create_backup_target, local_backup_target, BackupTarget,
+};
+use crate::application::backups::{verify_backup, write_backup_archive};
 use crate::application::database::DatabaseRootAccess;
 use crate::application::BackupLimit;
 use crate::config::MycologConfig;
+use crate::secrets::SecretsBackup;
 use crate::utils::asynchronous::run_catch;
 
+/// Maximum time for copying a backup to a single target and enforcing its limits.
+const TARGET_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
 pub async fn backup_service(
     config: &MycologConfig,
     db: DatabaseRootAccess,
+    secrets: &SecretsBackup,
     shutdown_token: CancellationToken,
 ) -> anyhow::Result<()> {
     info!("started backup service");
 
+    let backup_key = secrets.key();
+    let mut targets: Vec<(Box<dyn BackupTarget>, BackupLimit)> = Vec::new();
+    for target_config in &config.backup_targets {
+        let name_taken = target_config.name == "local"
+            || targets
+                .iter()
+                .any(|(target, _)| target.name() == target_config.name);
+        if name_taken {
+            error!(
+                name = target_config.name,
+                "backup target name is already taken and thus skipped"
+            );
+            continue;
+        }
+        match create_backup_target(target_config, secrets) {
+            Ok(target) => targets.push((target, target_config.limit.clone())),
+            Err(err) => error!(?err, "backup target unavailable and thus skipped"),
+        }
+    }
+
     // Initial delay
     let delay_duration = Duration::from_secs(config.backup_delay_hours * 3600);
 
@@ -38,9 +67,10 @@ pub async fn backup_service(
             _ = shutdown_token.cancelled() => break,
             _ = interval.tick() => {
                 info!("backing up database...");
-                let backup_result = backup_database(&db, &config.backup_limit).await;
-                if let Err(err) = backup_result {
-                    error!("database backup with error: {err}");
+                let backup_result = backup_database(&db, backup_key.as_ref(), config).await;
+                match backup_result {
+                    Ok(file_path) => copy_to_targets(&file_path, &targets, &shutdown_token).await,
+                    Err(err) => error!("database backup with error: {err}"),
                 }
             }
         }
@@ -49,94 +79,117 @@ pub async fn backup_service(
     Ok(())
 }
 
+/// Writes and verifies a local backup, returning its path.
 #[instrument(skip_all)]
 async fn backup_database(
     surreal: &DatabaseRootAccess,
-    backup_limit: &BackupLimit,
-) -> anyhow::Result<()> {
-    let file_path = calc_backup_file_name();
-
-    let mut target_file = tokio::fs::File::create(file_path.clone()).await?;
-    let mut compressed_reader = surreal.backup().await?;
-    tokio::io::copy_buf(&mut compressed_reader, &mut target_file).await?;
+    backup_key: Option<&[u8; 32]>,
+    config: &MycologConfig,
+) -> anyhow::Result<PathBuf> {
+    let file_path = calc_backup_file_name(&config.backups_dir);
+
+    // Write under a temporary name first, so failed backups never count toward the limits
+    let mut partial_path = file_path.clone().into_os_string();
+    partial_path.push(".partial");
+    let partial_path = PathBuf::from(partial_path);
+    let written: anyhow::Result<()> = async {
+        let target_file = tokio::fs::File::create(&partial_path).await?;
+        write_backup_archive(surreal, &config.images_dir, backup_key, target_file).await?;
+        tokio::fs::rename(&partial_path, &file_path).await?;
+        Ok(())
+    }
+    .await;
+    if let Err(err) = written {
+        if let Err(remove_err) = tokio::fs::remove_file(&partial_path).await {
+            warn!(?remove_err, file = %partial_path.display(), "unable to remove failed backup");
+        }
+        return Err(err);
+    }
     info!(
         "database backup written to: {:?}",
         file_path.file_name().ok_or(anyhow!("no filename"))?
     );
 
-    match constraint_backups(backup_limit).await {
+    // Verification failures are logged and recorded, they do not invalidate the backup
+    if let Err(err) = verify_backup(surreal, backup_key, &file_path).await {
+        error!(?err, "unable to verify database backup");
+    }
+
+    log_constrained_backups(
+        &local_backup_target(&config.backups_dir),
+        &config.backup_limit,
+    )
+    .await;
+
+    Ok(file_path)
+}
+
+/// Copies the local backup to every target. A failing or hanging target neither affects the
+/// local backup nor any other target, shutting down aborts the remaining copies.
+async fn copy_to_targets(
+    file_path: &Path,
+    targets: &[(Box<dyn BackupTarget>, BackupLimit)],
+    shutdown_token: &CancellationToken,
+) {
+    for (target, limit) in targets {
+        let span = info_span!("backup_target", name = target.name());
+        async {
+            let copy = async {
+                target.upload(file_path).await?;
+                info!("backup copied to target");
+                log_constrained_backups(target.as_ref(), limit).await;
+                anyhow::Ok(())
+            };
+            tokio::select! {
+                _ = shutdown_token.cancelled() => {
+                    warn!("copying backup to target aborted due to shutdown");
+                }
+                result = timeout(TARGET_TIMEOUT, copy) => match result {
+                    Ok(Ok(())) => {}
+                    Ok(Err(err)) => error!(?err, "copying backup to target failed"),
+                    Err(_) => error!(
+                        timeout_secs = TARGET_TIMEOUT.as_secs(),
+                        "copying backup to target timed out"
+                    ),
+                },
+            }
+        }
+        .instrument(span)
+        .await;
+
+        if shutdown_token.is_cancelled() {
+            break;
+        }
+    }
+}
+
+async fn log_constrained_backups(target: &dyn BackupTarget, limit: &BackupLimit) {
+    match constraint_backups(target, limit).await {
         Ok(backups_deleted) => {
             if backups_deleted > 0 {
                 info!(
                     amount = backups_deleted,
+                    backup_target = target.name(),
                     "previous database backups were deleted according to limit"
                 );
             }
         }
         Err(err) => {
-            error!(err = err.to_string(), "enforcing backup limits failed");
+            error!(
+                err = err.to_string(),
+                backup_target = target.name(),
+                "enforcing backup limits failed"
+            );
         }
     }
-
-    Ok(())
 }
 
-fn calc_backup_file_name() -> PathBuf {
+fn calc_backup_file_name(backups_dir: &Path) -> PathBuf {
     let now = Local::now();
-    let time = now.format("%Y%m%d%H%M%S").to_string();
+    let time = now.format(BACKUP_TIME_FORMAT).to_string();
     let file_name = format!("backup_{time}");
 
-    let mut file_path = Path::new("backups").join(file_name.clone());
-    file_path.set_extension("br");
+    let mut file_path = backups_dir.join(file_name.clone());
+    file_path.set_extension("tar.br");
     file_path
 }
-
-async fn get_backup_paths() -> anyhow::Result<Vec<PathBuf>> {
-    let mut valid_filenames = Path::new("backups/")
-        .read_dir()?
-        .filter_map(|entry| match entry {
-            Ok(entry) => {
-                let file_path = entry.path();
-                let file_name = file_path.file_name()?;
-                if !file_name.to_str()?.starts_with("backup_") {
-                    return None;
-                }
-                Some(file_path)
-            }
-            Err(err) => {
-                warn!(err = err.to_string(), "backup dir entry error");
-                None
-            }
-        })
-        .collect::<Vec<_>>();
-    valid_filenames.sort();
-    Ok(valid_filenames)
-}
-
-/// Ensures the backup limits are respected
-async fn constraint_backups(limit: &BackupLimit) -> anyhow::Result<u32> {
-    let mut count = 0;
-    let mut paths = VecDeque::from(get_backup_paths().await?);
-    while limit.check_exceeded(paths.make_contiguous()) {
-        if paths.len() <= 1 {
-            warn!("only backup should be deleted according to limit, ignoring...");
-            break;
-        }
-
-        delete_oldest_backup(&mut paths).await?;
-        count += 1;
-    }
-
-    Ok(count)
-}
-
-async fn delete_oldest_backup(paths: &mut VecDeque<PathBuf>) -> anyhow::Result<()> {
-    let backup_path = paths.pop_front();
-    if let Some(path) = backup_path {
-        tokio::fs::remove_file(path).await?;
-    } else {
-        bail!("no backup remaining to delete");
-    }
-
-    Ok(())
-}
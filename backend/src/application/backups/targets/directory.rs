@@ -0,0 +1,76 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use tracing::warn;
+
+use crate::application::backups::limits::BackupFile;
+use crate::application::backups::targets::BackupTarget;
+
+/// Stores backups inside a directory of the local file system.
+pub struct DirectoryTarget {
+    name: String,
+    folder: PathBuf,
+}
+
+impl DirectoryTarget {
+    pub fn new(name: impl Into<String>, folder: impl Into<PathBuf>) -> Self {
+        Self {
+            name: name.into(),
+            folder: folder.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl BackupTarget for DirectoryTarget {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn upload(&self, backup_path: &Path) -> anyhow::Result<()> {
+        let file_name = backup_path
+            .file_name()
+            .ok_or(anyhow!("backup path has no file name"))?;
+        tokio::fs::create_dir_all(&self.folder).await?;
+
+        // Copy under a temporary name first, so partial copies are never mistaken for backups
+        let mut partial_name = file_name.to_os_string();
+        partial_name.push(".partial");
+        let partial_path = self.folder.join(partial_name);
+        tokio::fs::copy(backup_path, &partial_path).await?;
+        tokio::fs::rename(&partial_path, self.folder.join(file_name)).await?;
+
+        Ok(())
+    }
+
+    async fn list(&self) -> anyhow::Result<Vec<BackupFile>> {
+        let mut backup_files = Vec::new();
+        let mut entries = tokio::fs::read_dir(&self.folder).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let Ok(file_name) = entry.file_name().into_string() else {
+                continue;
+            };
+            if !file_name.starts_with("backup_") || file_name.ends_with(".partial") {
+                continue;
+            }
+            let size = entry.metadata().await?.len();
+            let Some(backup_file) = BackupFile::parse(&file_name, size) else {
+                warn!(
+                    file = file_name,
+                    "unable to parse backup time from file name, ignoring..."
+                );
+                continue;
+            };
+
+            backup_files.push(backup_file);
+        }
+
+        Ok(backup_files)
+    }
+
+    async fn delete(&self, file_name: &str) -> anyhow::Result<()> {
+        tokio::fs::remove_file(self.folder.join(file_name)).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,116 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use chrono::Local;
+use tracing::debug;
+
+use crate::application::backups::limits::BackupFile;
+use crate::application::backups::targets::directory::DirectoryTarget;
+use crate::application::backups::targets::rsync::RsyncTarget;
+use crate::application::backups::targets::s3::S3Target;
+use crate::application::BackupLimit;
+use crate::secrets::SecretsBackup;
+
+mod directory;
+mod rsync;
+mod s3;
+
+/// A location backups are copied to in addition to the local backups directory.
+#[async_trait]
+pub trait BackupTarget: Send + Sync {
+    /// Name of the target as configured.
+    fn name(&self) -> &str;
+
+    /// Copies the local backup file to this target, keeping its file name.
+    async fn upload(&self, backup_path: &Path) -> anyhow::Result<()>;
+
+    /// All backups currently stored on this target.
+    async fn list(&self) -> anyhow::Result<Vec<BackupFile>>;
+
+    /// Deletes the backup with the given file name from this target.
+    async fn delete(&self, file_name: &str) -> anyhow::Result<()>;
+}
+
+/// Configuration of a single backup target including its own retention rules.
+#[derive(Clone, Debug)]
+pub struct BackupTargetConfig {
+    pub name: String,
+    pub kind: BackupTargetKind,
+    pub limit: BackupLimit,
+}
+
+#[derive(Clone, Debug)]
+pub enum BackupTargetKind {
+    /// Another directory, e.g. a mounted network share.
+    Directory { path: PathBuf },
+    /// A directory on a remote host, reached via rsync and ssh.
+    Rsync { host: String, path: String },
+    /// A bucket of S3 compatible object storage.
+    S3 {
+        endpoint: String,
+        region: String,
+        bucket: String,
+        prefix: String,
+    },
+}
+
+/// The local backups directory, which every backup is written to first.
+pub fn local_backup_target(backups_dir: &Path) -> DirectoryTarget {
+    DirectoryTarget::new("local", backups_dir)
+}
+
+pub fn create_backup_target(
+    config: &BackupTargetConfig,
+    secrets: &SecretsBackup,
+) -> anyhow::Result<Box<dyn BackupTarget>> {
+    let name = config.name.clone();
+    let target: Box<dyn BackupTarget> = match &config.kind {
+        BackupTargetKind::Directory { path } => Box::new(DirectoryTarget::new(name, path)),
+        BackupTargetKind::Rsync { host, path } => {
+            Box::new(RsyncTarget::new(name, host.clone(), path.clone()))
+        }
+        BackupTargetKind::S3 {
+            endpoint,
+            region,
+            bucket,
+            prefix,
+        } => {
+            let credentials = secrets.target_credentials(&name).ok_or(anyhow!(
+                "no credentials for backup target `{name}` configured in secrets"
+            ))?;
+            Box::new(S3Target::new(
+                name,
+                endpoint.clone(),
+                region.clone(),
+                bucket.clone(),
+                prefix.clone(),
+                credentials,
+            )?)
+        }
+    };
+
+    Ok(target)
+}
+
+/// Ensures the backup limits are respected on the given target.
+/// Returns the amount of deleted backups.
+pub async fn constraint_backups(
+    target: &dyn BackupTarget,
+    limit: &BackupLimit,
+) -> anyhow::Result<u32> {
+    let mut count = 0;
+    let mut backups = target.list().await?;
+    backups.sort_by_key(|backup| backup.time);
+    for backup in limit.expired(&backups, Local::now()) {
+        debug!(
+            backup_target = target.name(),
+            file = backup.file_name,
+            "deleting backup according to limit"
+        );
+        target.delete(&backup.file_name).await?;
+        count += 1;
+    }
+
+    Ok(count)
+}
@@ -0,0 +1,124 @@
+use std::path::Path;
+use std::process::Output;
+
+use anyhow::{anyhow, bail};
+use async_trait::async_trait;
+use tokio::process::Command;
+use tracing::warn;
+
+use crate::application::backups::limits::BackupFile;
+use crate::application::backups::targets::BackupTarget;
+
+/// Stores backups in a directory of a remote host. Uploads use `rsync`, listing and deleting
+/// backups use `ssh`. Authentication has to work non-interactively, e.g. with a key file.
+pub struct RsyncTarget {
+    name: String,
+    host: String,
+    path: String,
+}
+
+impl RsyncTarget {
+    pub fn new(name: String, host: String, path: String) -> Self {
+        Self { name, host, path }
+    }
+
+    async fn ssh(&self, remote_command: String) -> anyhow::Result<Output> {
+        let output = Command::new("ssh")
+            .args(["-o", "BatchMode=yes", &self.host, "--", &remote_command])
+            .kill_on_drop(true)
+            .output()
+            .await
+            .map_err(|err| anyhow!("unable to run ssh: {:?}", err))?;
+        if !output.status.success() {
+            bail!(
+                "ssh command on {} failed with {}: {}",
+                self.host,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(output)
+    }
+
+    fn remote_path(&self, file_name: &str) -> String {
+        format!("{}/{}", self.path.trim_end_matches('/'), file_name)
+    }
+}
+
+#[async_trait]
+impl BackupTarget for RsyncTarget {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn upload(&self, backup_path: &Path) -> anyhow::Result<()> {
+        self.ssh(format!("mkdir -p {}", shell_quote(&self.path)))
+            .await?;
+
+        let destination = format!("{}:{}/", self.host, self.path.trim_end_matches('/'));
+        // Rsync writes into a temporary file and renames it once the transfer is complete
+        let output = Command::new("rsync")
+            .args(["--times", "-e", "ssh -o BatchMode=yes"])
+            .arg(backup_path)
+            .arg(destination)
+            .kill_on_drop(true)
+            .output()
+            .await
+            .map_err(|err| anyhow!("unable to run rsync: {:?}", err))?;
+        if !output.status.success() {
+            bail!(
+                "rsync to {} failed with {}: {}",
+                self.host,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        Ok(())
+    }
+
+    async fn list(&self) -> anyhow::Result<Vec<BackupFile>> {
+        let output = self
+            .ssh(format!(
+                "find {} -maxdepth 1 -type f -name 'backup_*' -printf '%f %s\\n'",
+                shell_quote(&self.path)
+            ))
+            .await?;
+
+        let mut backup_files = Vec::new();
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            let Some((file_name, size)) = line.rsplit_once(' ') else {
+                continue;
+            };
+            let backup_file = size
+                .parse()
+                .ok()
+                .and_then(|size| BackupFile::parse(file_name, size));
+            let Some(backup_file) = backup_file else {
+                warn!(
+                    backup_target = self.name,
+                    file = file_name,
+                    "unable to parse remote backup file, ignoring..."
+                );
+                continue;
+            };
+            backup_files.push(backup_file);
+        }
+
+        Ok(backup_files)
+    }
+
+    async fn delete(&self, file_name: &str) -> anyhow::Result<()> {
+        self.ssh(format!(
+            "rm -f -- {}",
+            shell_quote(&self.remote_path(file_name))
+        ))
+        .await?;
+        Ok(())
+    }
+}
+
+/// Quotes the value for a POSIX shell, as ssh passes the remote command to the login shell.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
@@ -0,0 +1,462 @@
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail};
+use async_trait::async_trait;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use lazy_static::lazy_static;
+use regex::Regex;
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_LENGTH};
+use reqwest::{Body, Client, Method, Url};
+use sha2::{Digest, Sha256};
+use tokio_util::io::ReaderStream;
+use tracing::warn;
+
+use crate::application::backups::limits::BackupFile;
+use crate::application::backups::targets::BackupTarget;
+use crate::secrets::BackupTargetCredentials;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Maximum time for establishing a connection to the endpoint.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Maximum time without receiving any data, so stalled transfers are aborted.
+const READ_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+lazy_static! {
+    static ref CONTENTS_REGEX: Regex =
+        Regex::new(r"(?s)<Contents>.*?<Key>(.*?)</Key>.*?<Size>(\d+)</Size>.*?</Contents>")
+            .expect("contents regex is valid");
+}
+
+/// Stores backups in a bucket of S3 compatible object storage (e.g. AWS S3 or MinIO).
+/// Requests use path-style addressing and AWS Signature Version 4.
+pub struct S3Target {
+    name: String,
+    client: Client,
+    endpoint: Url,
+    region: String,
+    bucket: String,
+    prefix: String,
+    credentials: BackupTargetCredentials,
+}
+
+impl S3Target {
+    pub fn new(
+        name: String,
+        endpoint: String,
+        region: String,
+        bucket: String,
+        prefix: String,
+        credentials: BackupTargetCredentials,
+    ) -> anyhow::Result<Self> {
+        let endpoint = Url::parse(&endpoint)
+            .map_err(|err| anyhow!("invalid endpoint of backup target `{name}`: {err}"))?;
+
+        let client = Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .read_timeout(READ_TIMEOUT)
+            .build()?;
+
+        Ok(Self {
+            name,
+            client,
+            endpoint,
+            region,
+            bucket,
+            prefix: prefix.trim_matches('/').to_string(),
+            credentials,
+        })
+    }
+
+    fn object_key(&self, file_name: &str) -> String {
+        if self.prefix.is_empty() {
+            file_name.to_string()
+        } else {
+            format!("{}/{}", self.prefix, file_name)
+        }
+    }
+
+    /// Creates a signed request for the given object key (or the bucket itself if [None]).
+    fn request(
+        &self,
+        method: Method,
+        object_key: Option<&str>,
+        query: &[(&str, &str)],
+    ) -> anyhow::Result<reqwest::RequestBuilder> {
+        let mut canonical_uri = format!(
+            "{}/{}",
+            self.endpoint.path().trim_end_matches('/'),
+            uri_encode(&self.bucket, true)
+        );
+        if let Some(object_key) = object_key {
+            canonical_uri.push('/');
+            canonical_uri.push_str(&uri_encode(object_key, false));
+        }
+        let canonical_query = canonical_query(query);
+
+        let mut url = self.endpoint.clone();
+        url.set_path(&canonical_uri);
+        if canonical_query.is_empty() {
+            url.set_query(None);
+        } else {
+            url.set_query(Some(&canonical_query));
+        }
+        let host = match url.port() {
+            Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+            None => url.host_str().unwrap_or_default().to_string(),
+        };
+
+        let amz_date = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        // Payloads are streamed, so they are not part of the signature
+        let payload_hash = "UNSIGNED-PAYLOAD";
+
+        let canonical_request = canonical_request(
+            method.as_str(),
+            &canonical_uri,
+            &canonical_query,
+            &host,
+            &amz_date,
+            payload_hash,
+        );
+        let (scope, signature) = sign(
+            &self.credentials.secret_key(),
+            &self.region,
+            &amz_date,
+            &canonical_request,
+        )?;
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-amz-date", HeaderValue::from_str(&amz_date)?);
+        headers.insert(
+            "x-amz-content-sha256",
+            HeaderValue::from_static(payload_hash),
+        );
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_str(&format!(
+                "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={SIGNED_HEADERS}, Signature={signature}",
+                self.credentials.access_key()
+            ))?,
+        );
+
+        Ok(self.client.request(method, url).headers(headers))
+    }
+}
+
+#[async_trait]
+impl BackupTarget for S3Target {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn upload(&self, backup_path: &Path) -> anyhow::Result<()> {
+        let file_name = backup_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or(anyhow!("backup path has no valid file name"))?;
+        let backup_file = tokio::fs::File::open(backup_path).await?;
+        let size = backup_file.metadata().await?.len();
+
+        let response = self
+            .request(Method::PUT, Some(&self.object_key(file_name)), &[])?
+            .header(CONTENT_LENGTH, size)
+            .body(Body::wrap_stream(ReaderStream::new(backup_file)))
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            bail!(
+                "upload to bucket `{}` failed with {status}: {}",
+                self.bucket,
+                response.text().await.unwrap_or_default()
+            );
+        }
+
+        Ok(())
+    }
+
+    async fn list(&self) -> anyhow::Result<Vec<BackupFile>> {
+        let prefix = self.object_key("backup_");
+        let mut backup_files = Vec::new();
+        let mut continuation_token: Option<String> = None;
+        loop {
+            let mut query = vec![("list-type", "2"), ("prefix", prefix.as_str())];
+            if let Some(token) = &continuation_token {
+                query.push(("continuation-token", token.as_str()));
+            }
+            let response = self.request(Method::GET, None, &query)?.send().await?;
+            let status = response.status();
+            if !status.is_success() {
+                bail!(
+                    "listing bucket `{}` failed with {status}: {}",
+                    self.bucket,
+                    response.text().await.unwrap_or_default()
+                );
+            }
+            let page = parse_list_objects(&response.text().await?);
+
+            for key in page.ignored_keys {
+                warn!(
+                    backup_target = self.name,
+                    key, "unable to parse remote backup object, ignoring..."
+                );
+            }
+            backup_files.extend(page.backup_files);
+
+            continuation_token = page.continuation_token;
+            if continuation_token.is_none() {
+                break;
+            }
+        }
+
+        Ok(backup_files)
+    }
+
+    async fn delete(&self, file_name: &str) -> anyhow::Result<()> {
+        let response = self
+            .request(Method::DELETE, Some(&self.object_key(file_name)), &[])?
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            bail!(
+                "deleting from bucket `{}` failed with {}",
+                self.bucket,
+                response.status()
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Headers included in every signature, [canonical_request] lists exactly these.
+const SIGNED_HEADERS: &str = "host;x-amz-content-sha256;x-amz-date";
+
+fn canonical_query(query: &[(&str, &str)]) -> String {
+    let mut query = query
+        .iter()
+        .map(|(key, value)| (uri_encode(key, true), uri_encode(value, true)))
+        .collect::<Vec<_>>();
+    query.sort();
+    query
+        .iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn canonical_request(
+    method: &str,
+    canonical_uri: &str,
+    canonical_query: &str,
+    host: &str,
+    amz_date: &str,
+    payload_hash: &str,
+) -> String {
+    format!(
+        "{method}\n{canonical_uri}\n{canonical_query}\nhost:{host}\nx-amz-content-sha256:{payload_hash}\nx-amz-date:{amz_date}\n\n{SIGNED_HEADERS}\n{payload_hash}"
+    )
+}
+
+/// Signs the canonical request with a key derived from the secret key, returning the credential
+/// scope and the signature.
+fn sign(
+    secret_key: &str,
+    region: &str,
+    amz_date: &str,
+    canonical_request: &str,
+) -> anyhow::Result<(String, String)> {
+    let date = amz_date
+        .get(..8)
+        .ok_or(anyhow!("invalid request date `{amz_date}`"))?;
+    let scope = format!("{date}/{region}/s3/aws4_request");
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let secret_key = format!("AWS4{secret_key}");
+    let mut signing_key = hmac_sha256(secret_key.as_bytes(), date.as_bytes())?;
+    signing_key = hmac_sha256(&signing_key, region.as_bytes())?;
+    signing_key = hmac_sha256(&signing_key, b"s3")?;
+    signing_key = hmac_sha256(&signing_key, b"aws4_request")?;
+    let signature = hex::encode(hmac_sha256(&signing_key, string_to_sign.as_bytes())?);
+
+    Ok((scope, signature))
+}
+
+/// A single page of a `ListObjectsV2` response.
+struct ListObjectsPage {
+    backup_files: Vec<BackupFile>,
+    /// Keys of listed objects which are no backups.
+    ignored_keys: Vec<String>,
+    continuation_token: Option<String>,
+}
+
+fn parse_list_objects(body: &str) -> ListObjectsPage {
+    let mut backup_files = Vec::new();
+    let mut ignored_keys = Vec::new();
+    for captures in CONTENTS_REGEX.captures_iter(body) {
+        let key = &captures[1];
+        let file_name = key.rsplit('/').next().unwrap_or(key);
+        let backup_file = captures[2]
+            .parse()
+            .ok()
+            .and_then(|size| BackupFile::parse(file_name, size));
+        match backup_file {
+            Some(backup_file) => backup_files.push(backup_file),
+            None => ignored_keys.push(key.to_string()),
+        }
+    }
+
+    ListObjectsPage {
+        backup_files,
+        ignored_keys,
+        continuation_token: xml_value(body, "NextContinuationToken"),
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut hmac =
+        HmacSha256::new_from_slice(key).map_err(|_| anyhow!("invalid hmac key length"))?;
+    hmac.update(data);
+    Ok(hmac.finalize().into_bytes().to_vec())
+}
+
+/// Percent encodes everything except unreserved characters as required by Signature Version 4.
+/// Slashes are kept unless `encode_slash` is set.
+fn uri_encode(value: &str, encode_slash: bool) -> String {
+    let mut encoded = String::new();
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if !encode_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+fn xml_value(xml: &str, tag: &str) -> Option<String> {
+    let start_tag = format!("<{tag}>");
+    let start = xml.find(&start_tag)? + start_tag.len();
+    let end = xml[start..].find(&format!("</{tag}>"))? + start;
+    Some(xml[start..end].replace("&amp;", "&"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EMPTY_PAYLOAD_HASH: &str =
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+    /// "GET Bucket (List Objects)" example of the AWS Signature Version 4 documentation.
+    #[test]
+    fn signs_aws_reference_request() {
+        let canonical_query = canonical_query(&[("prefix", "J"), ("max-keys", "2")]);
+        assert_eq!(canonical_query, "max-keys=2&prefix=J");
+
+        let canonical_request = canonical_request(
+            "GET",
+            "/",
+            &canonical_query,
+            "examplebucket.s3.amazonaws.com",
+            "20130524T000000Z",
+            EMPTY_PAYLOAD_HASH,
+        );
+        let (scope, signature) = sign(
+            "wJalrXUtnFEMI/K7MDENG/bPxRfiCYEXAMPLEKEY",
+            "us-east-1",
+            "20130524T000000Z",
+            &canonical_request,
+        )
+        .unwrap();
+
+        assert_eq!(scope, "20130524/us-east-1/s3/aws4_request");
+        assert_eq!(
+            signature,
+            "34b48302e7b5fa45bde8084f4b7868a86f0a534bc59db6670ed5711ef69dc6f7"
+        );
+    }
+
+    #[test]
+    fn encodes_uri_components() {
+        assert_eq!(uri_encode("backups/2024 01", false), "backups/2024%2001");
+        assert_eq!(uri_encode("backups/2024 01", true), "backups%2F2024%2001");
+        assert_eq!(uri_encode("a-b_c.d~e", true), "a-b_c.d~e");
+        assert_eq!(
+            canonical_query(&[("list-type", "2"), ("continuation-token", "a/b=")]),
+            "continuation-token=a%2Fb%3D&list-type=2"
+        );
+    }
+
+    #[test]
+    fn parses_list_objects_response() {
+        let body = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ListBucketResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+    <Name>backups</Name>
+    <Prefix>mycolog/backup_</Prefix>
+    <KeyCount>3</KeyCount>
+    <MaxKeys>3</MaxKeys>
+    <IsTruncated>true</IsTruncated>
+    <Contents>
+        <Key>mycolog/backup_20240101120000.tar.br</Key>
+        <LastModified>2024-01-01T12:00:05.000Z</LastModified>
+        <ETag>&quot;9b2cf535f27731c974343645a3985328&quot;</ETag>
+        <Size>1048576</Size>
+        <StorageClass>STANDARD</StorageClass>
+    </Contents>
+    <Contents>
+        <Key>mycolog/backup_20231231120000.br</Key>
+        <LastModified>2023-12-31T12:00:03.000Z</LastModified>
+        <ETag>&quot;5f27731c974343645a39853289b2cf53&quot;</ETag>
+        <Size>2048</Size>
+        <StorageClass>STANDARD</StorageClass>
+    </Contents>
+    <Contents>
+        <Key>mycolog/backup_notes.txt</Key>
+        <LastModified>2024-01-02T08:00:00.000Z</LastModified>
+        <ETag>&quot;74343645a39853289b2cf535f27731c9&quot;</ETag>
+        <Size>12</Size>
+        <StorageClass>STANDARD</StorageClass>
+    </Contents>
+    <NextContinuationToken>1ueGcxLPRx1Tr/XYExHnhbYLgveDs2J/wm36Hy4vbOwM=&amp;</NextContinuationToken>
+</ListBucketResult>"#;
+
+        let page = parse_list_objects(body);
+
+        let backups = page
+            .backup_files
+            .iter()
+            .map(|backup| (backup.file_name.as_str(), backup.size))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            backups,
+            [
+                ("backup_20240101120000.tar.br", 1048576),
+                ("backup_20231231120000.br", 2048)
+            ]
+        );
+        assert_eq!(page.ignored_keys, ["mycolog/backup_notes.txt"]);
+        assert_eq!(
+            page.continuation_token.as_deref(),
+            Some("1ueGcxLPRx1Tr/XYExHnhbYLgveDs2J/wm36Hy4vbOwM=&")
+        );
+    }
+
+    #[test]
+    fn parses_last_list_objects_page() {
+        let body = r#"<ListBucketResult><KeyCount>0</KeyCount><IsTruncated>false</IsTruncated></ListBucketResult>"#;
+
+        let page = parse_list_objects(body);
+
+        assert!(page.backup_files.is_empty());
+        assert!(page.ignored_keys.is_empty());
+        assert_eq!(page.continuation_token, None);
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::io::BufReader;
+use tracing::{error, info, instrument};
+
+use crate::application::backups::archive::restore_backup_in_memory;
+use crate::application::database::DatabaseRootAccess;
+
+/// Result of test-restoring a backup into a throwaway in-memory datastore.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BackupVerification {
+    pub file_name: String,
+    pub success: bool,
+    pub error: Option<String>,
+    /// Tables whose record count after restoring differs from the count recorded in the backup.
+    pub mismatches: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_verified: Option<DateTime<Utc>>,
+}
+
+/// Verifies the given backup can be restored and yields the amount of records it was written with.
+/// The result is stored in the `backup_verification` table.
+#[instrument(skip_all, fields(file = %backup_path.display()))]
+pub async fn verify_backup(
+    db: &DatabaseRootAccess,
+    key: Option<&[u8; 32]>,
+    backup_path: &Path,
+) -> anyhow::Result<BackupVerification> {
+    let file_name = backup_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or(anyhow!("backup path has no valid file name"))?
+        .to_string();
+
+    let verification = match test_restore(key, backup_path).await {
+        Ok(mismatches) => BackupVerification {
+            file_name,
+            success: mismatches.is_empty(),
+            error: None,
+            mismatches,
+            time_verified: None,
+        },
+        Err(err) => BackupVerification {
+            file_name,
+            success: false,
+            error: Some(err.to_string()),
+            mismatches: Vec::new(),
+            time_verified: None,
+        },
+    };
+
+    if verification.success {
+        info!("backup verified successfully");
+    } else {
+        error!(
+            error = ?verification.error,
+            mismatches = ?verification.mismatches,
+            "BACKUP VERIFICATION FAILED, the backup may not be restorable"
+        );
+    }
+
+    db.query("CREATE backup_verification CONTENT $verification;")
+        .bind("verification", &verification)
+        .await?
+        .checked()?;
+
+    Ok(verification)
+}
+
+/// Restores the backup into an in-memory datastore and returns all record count mismatches.
+async fn test_restore(key: Option<&[u8; 32]>, backup_path: &Path) -> anyhow::Result<Vec<String>> {
+    let backup_file = tokio::fs::File::open(backup_path).await?;
+    let staging_folder = backup_path.with_file_name(".verification");
+    let restore =
+        restore_backup_in_memory(&staging_folder, key, BufReader::new(backup_file)).await?;
+
+    let restored_counts = restore.db.record_counts().await?;
+    let expected_counts = restore.record_counts;
+
+    let tables = restored_counts
+        .keys()
+        .chain(expected_counts.keys())
+        .collect::<BTreeSet<_>>();
+    let mut mismatches = Vec::new();
+    for table in tables {
+        let restored_count = restored_counts.get(table).copied().unwrap_or(0);
+        let expected_count = expected_counts.get(table).copied().unwrap_or(0);
+        if restored_count != expected_count {
+            mismatches.push(format!(
+                "table `{table}` has {restored_count} records after restoring but {expected_count} were backed up"
+            ));
+        }
+    }
+
+    Ok(mismatches)
+}
+
+/// The most recent backup verifications, newest first.
+pub async fn recent_backup_verifications(
+    db: &DatabaseRootAccess,
+    limit: u32,
+) -> anyhow::Result<Vec<BackupVerification>> {
+    db.query("SELECT * FROM backup_verification ORDER BY time_verified DESC LIMIT $limit;")
+        .bind("limit", limit)
+        .await?
+        .take(0)
+}
@@ -0,0 +1,31 @@
+use std::path::PathBuf;
+
+/// Storage engine of the datastore. Engines other than [DatabaseEngine::Memory] keep the data
+/// in the given directory, they are only available if compiled in via the surrealdb features.
+#[derive(Clone, Debug)]
+pub enum DatabaseEngine {
+    /// Nothing is persisted, intended for tests and development.
+    Memory,
+    Speedb {
+        path: PathBuf,
+    },
+    Rocksdb {
+        path: PathBuf,
+    },
+    /// Alias of RocksDB kept by SurrealDB for compatibility.
+    File {
+        path: PathBuf,
+    },
+}
+
+impl DatabaseEngine {
+    /// The address understood by [surrealdb_core::kvs::Datastore::new].
+    pub fn address(&self) -> String {
+        match self {
+            DatabaseEngine::Memory => "memory".to_string(),
+            DatabaseEngine::Speedb { path } => format!("speedb://{}", path.display()),
+            DatabaseEngine::Rocksdb { path } => format!("rocksdb://{}", path.display()),
+            DatabaseEngine::File { path } => format!("file://{}", path.display()),
+        }
+    }
+}
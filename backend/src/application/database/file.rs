@@ -5,6 +5,7 @@ use anyhow::anyhow;
 use surrealdb_core::sql::Statements;
 
 pub struct SqlFile {
+    pub content: String,
     pub statements: Statements,
 }
 
@@ -19,6 +20,7 @@ pub async fn load_surql_file(path: impl Borrow<PathBuf>) -> anyhow::Result<SqlFi
     })?;
     if content.trim().is_empty() {
         return Ok(SqlFile {
+            content,
             statements: Statements(Vec::new()),
         });
     }
@@ -32,6 +34,7 @@ pub async fn load_surql_file(path: impl Borrow<PathBuf>) -> anyhow::Result<SqlFi
     })?;
 
     Ok(SqlFile {
+        content,
         statements: query.0,
     })
 }
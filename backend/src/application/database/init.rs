@@ -1,9 +1,14 @@
-use anyhow::bail;
+use anyhow::{anyhow, bail};
+use chrono::Local;
 use surrealdb_core::kvs::Datastore;
-use tracing::{error, info_span, instrument, Instrument};
+use tokio::io::BufReader;
+use tracing::{error, info, info_span, instrument, Instrument};
 
+use crate::application::backups::restore_backup_archive;
 use crate::application::database::migration::MigrationManager;
 use crate::application::database::system::DatabaseSystem;
+use crate::application::database::DatabaseRootAccess;
+use crate::application::ImageManager;
 use crate::config::MycologConfig;
 use crate::context::MycologContext;
 use crate::secrets::MycologSecrets;
@@ -13,18 +18,16 @@ pub async fn create_database_system(
     config: &MycologConfig,
     secrets: &MycologSecrets,
 ) -> anyhow::Result<DatabaseSystem> {
-    let db = DatabaseSystem::create(
-        "timerertim",
-        "mycolog",
-        &secrets.db.user(),
-        &secrets.db.password(),
-    )
-    .await?;
+    let db = open_database_system(config, secrets).await?;
 
     let root_db = db.auth_root();
 
+    import_drop_file(config, &root_db, secrets.backup.key().as_ref())
+        .instrument(info_span!("database_import"))
+        .await?;
+
     async {
-        let manager = match MigrationManager::new("migrations/").await {
+        let manager = match MigrationManager::new(&config.migrations_dir).await {
             Ok(manager) => manager,
             Err(err) => {
                 error!(%err, "migrations failed to load due to error");
@@ -32,7 +35,16 @@ pub async fn create_database_system(
             }
         };
 
-        if let Err(err) = manager.apply_migrations(&root_db).await {
+        // The image manager is created after the migrations, so Rust migrations get their own
+        let images = ImageManager::new(
+            &config.images_dir,
+            db.auth_root(),
+            config.images_max_bytes_per_user,
+        )?;
+        if let Err(err) = manager
+            .apply_migrations(&root_db, Some(&images), config.migration_drift_policy)
+            .await
+        {
             error!(%err, "unable to setup database due to migration error");
             bail!(err);
         }
@@ -43,3 +55,61 @@ pub async fn create_database_system(
 
     Ok(db)
 }
+
+/// Opens the database without applying any migrations.
+#[instrument(skip_all)]
+pub async fn open_database_system(
+    config: &MycologConfig,
+    secrets: &MycologSecrets,
+) -> anyhow::Result<DatabaseSystem> {
+    DatabaseSystem::create(
+        &config.database_engine,
+        "timerertim",
+        "mycolog",
+        &secrets.db.user(),
+        &secrets.db.password(),
+    )
+    .await
+}
+
+/// Restores the database from a backup placed at `import.br` inside the data directory, if
+/// present. The file is renamed afterwards so it is only imported once.
+async fn import_drop_file(
+    config: &MycologConfig,
+    db: &DatabaseRootAccess,
+    backup_key: Option<&[u8; 32]>,
+) -> anyhow::Result<()> {
+    let import_path = config.data_dir.join("import.br");
+    if !import_path.is_file() {
+        return Ok(());
+    }
+
+    info!(file = %import_path.display(), "found database import file, restoring...");
+    let import_file = tokio::fs::File::open(&import_path).await?;
+    if let Err(err) = restore_backup_archive(
+        db,
+        &config.images_dir,
+        backup_key,
+        BufReader::new(import_file),
+    )
+    .await
+    {
+        error!(%err, "unable to restore database from import file");
+        bail!(err);
+    }
+
+    let time = Local::now().format("%Y%m%d%H%M%S").to_string();
+    let imported_path = import_path.with_file_name(format!("imported_{time}.br"));
+    tokio::fs::rename(import_path, &imported_path)
+        .await
+        .map_err(|err| {
+            anyhow!(
+                "database was restored but import file could not be renamed to {}: {:?}",
+                imported_path.display(),
+                err
+            )
+        })?;
+    info!(file = %imported_path.display(), "restored database from import file");
+
+    Ok(())
+}
@@ -1,48 +1,138 @@
 use std::ffi::OsString;
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::sync::Arc;
 
 use anyhow::{anyhow, bail};
 use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use surrealdb_core::sql::{Datetime, Number, Object, Statements, Value};
 use tracing::{debug, error, info, warn};
 
 use crate::application::database::file::load_surql_file;
+use crate::application::database::rust_migrations::{rust_migrations, RustMigration};
+use crate::application::database::version::{check_schema_version, schema_version};
 use crate::application::database::DatabaseRootAccess;
+use crate::application::ImageManager;
 use crate::utils::types::GenericTryInto;
 
+/// How to react to applied migrations which were changed or removed on the file system.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MigrationDriftPolicy {
+    /// Log every drifted migration and continue.
+    Warn,
+    /// Refuse to apply any migration, which stops the startup.
+    Refuse,
+}
+
+/// An applied migration which no longer matches its file.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MigrationDrift {
+    /// The file content changed after the migration was applied.
+    Modified {
+        file_name: String,
+        applied_checksum: String,
+        file_checksum: String,
+    },
+    /// The migration was applied but its file no longer exists.
+    Missing { file_name: String },
+}
+
+/// State of all migrations compared to the database.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MigrationPlan {
+    /// File names of the applied migrations, in order of their numbers.
+    pub applied: Vec<String>,
+    /// File names of the migrations which would be applied next, in order of their numbers.
+    pub pending: Vec<String>,
+    pub drifted: Vec<MigrationDrift>,
+    /// Current `$SCHEMA_VERSION` of the database, if defined.
+    pub schema_version: Option<String>,
+}
+
+/// A migration as recorded in the `migration` table.
+#[derive(Deserialize, Debug)]
+struct AppliedMigration {
+    file_name: String,
+    /// Missing for migrations applied before checksums were recorded.
+    checksum: Option<String>,
+}
+
 pub struct MigrationManager {
     folder: PathBuf,
     schema_file: SchemaFile,
-    migration_files: Vec<MigrationFile>,
+    migrations: Vec<Migration>,
 }
 
 pub struct SchemaFile {
     statements: Statements,
 }
 
-pub struct MigrationFile {
-    statements: Statements,
+pub struct Migration {
+    action: MigrationAction,
+    /// Name of the migration file, or `NNNN_name.rs` for Rust migrations.
     file_name: String,
     number: usize,
+    /// Hex encoded SHA-256 hash of the file content. Rust migrations use the hash of their
+    /// file name instead.
+    checksum: String,
+}
+
+enum MigrationAction {
+    Surql {
+        statements: Statements,
+        /// Statements of the paired `.down.surql` file reverting this migration, if any.
+        down_statements: Option<Statements>,
+    },
+    Rust(Arc<dyn RustMigration>),
 }
 
 impl MigrationManager {
     pub async fn new(folder: impl Into<PathBuf>) -> anyhow::Result<MigrationManager> {
         let folder = folder.into();
         let schema_file = load_schema_file(folder.clone()).await?;
-        let migration_files = load_migration_files(folder.clone()).await?;
+        let mut migrations = load_migration_files(folder.clone()).await?;
+        info!("found {} migration files", migrations.len());
+
+        for rust_migration in rust_migrations() {
+            let file_name = format!(
+                "{:04}_{}.rs",
+                rust_migration.number(),
+                rust_migration.name()
+            );
+            migrations.push(Migration {
+                checksum: hex::encode(Sha256::digest(file_name.as_bytes())),
+                file_name,
+                number: rust_migration.number(),
+                action: MigrationAction::Rust(rust_migration),
+            });
+        }
+        migrations.sort_by_key(|migration| migration.number);
 
-        info!("found {} migration files", migration_files.len());
+        // Checking migrations to ensure numerical consistency
+        for (index, migration) in migrations.iter().enumerate() {
+            if migration.number != index + 1 {
+                bail!("migrations do not start at 1, are more than one apart or share a number (at {})", migration.file_name);
+            }
+        }
 
         Ok(MigrationManager {
             folder,
             schema_file,
-            migration_files,
+            migrations,
         })
     }
 
-    pub async fn apply_migrations(&self, db: &DatabaseRootAccess) -> anyhow::Result<u32> {
+    /// Applies all pending migrations in order of their numbers. Rust migrations are skipped
+    /// with a warning if no image manager is given, which is only intended for dry runs.
+    pub async fn apply_migrations(
+        &self,
+        db: &DatabaseRootAccess,
+        images: Option<&ImageManager>,
+        drift_policy: MigrationDriftPolicy,
+    ) -> anyhow::Result<u32> {
         let mut db_info: Value = db
             .query("INFO FOR DB")
             .await?
@@ -57,27 +147,50 @@ impl MigrationManager {
             .try_into_type::<Object>()
             .map_err(|_| anyhow!("tables inside info for db is not an object"))?;
 
-        let has_migrations_table = tables.get("migrations").is_some();
+        let has_migrations_table = tables.get("migration").is_some();
         if !has_migrations_table {
             info!("database has no migration content, importing initial schema...");
             db.query("BEGIN TRANSACTION;")
                 .query(self.schema_file.statements.clone())
-                .query(
-                    r#"
-                    DEFINE TABLE migration SCHEMAFULL PERMISSIONS NONE;
-
-                    DEFINE FIELD file_name ON TABLE migration TYPE string;
-                    DEFINE FIELD number ON TABLE migration TYPE int;
-                    DEFINE FIELD exec_time ON TABLE migration TYPE datetime DEFAULT time::now();
-                "#,
-                )
+                .query(MIGRATION_TABLE)
                 .query("COMMIT TRANSACTION;")
                 .await?
                 .checked()?;
+        } else {
+            // Refuse before anything is changed if the schema is newer than supported
+            check_schema_version(db, false).await?;
+            // Adds fields introduced after the migration table was created
+            db.query(MIGRATION_TABLE).await?.checked()?;
         };
 
+        let drifts = self.drifts(db).await?;
+        for drift in &drifts {
+            match drift {
+                MigrationDrift::Modified { file_name, .. } => {
+                    warn!(file = %file_name, "migration file has been modified after it was applied")
+                }
+                MigrationDrift::Missing { file_name } => {
+                    warn!(file = %file_name, "applied migration file is missing on file system")
+                }
+            }
+        }
+        if !drifts.is_empty() && drift_policy == MigrationDriftPolicy::Refuse {
+            bail!(
+                "{} applied migrations do not match their files, refusing to migrate",
+                drifts.len()
+            );
+        }
+        // Migrations applied before checksums were recorded are assumed to be unchanged
+        for migration_file in &self.migrations {
+            db.query("UPDATE migration SET checksum = $checksum WHERE file_name = $file_name AND checksum = NONE;")
+                .bind("checksum", &migration_file.checksum)
+                .bind("file_name", &migration_file.file_name)
+                .await?
+                .checked()?;
+        }
+
         let mut migrated_count = 0;
-        for (index, migration_file) in self.migration_files.iter().enumerate() {
+        for (index, migration_file) in self.migrations.iter().enumerate() {
             let file_name = &migration_file.file_name;
             let migration: anyhow::Result<Value> = db
                 .query("SELECT exec_time, number FROM ONLY migration WHERE file_name = $migration_file_name LIMIT 1;")
@@ -119,24 +232,198 @@ impl MigrationManager {
                 continue;
             }
 
+            let record_statement = format!(
+                "CREATE migration SET file_name = $filename{0}, number = $number{0}, checksum = $checksum{0};",
+                index
+            );
+            match &migration_file.action {
+                MigrationAction::Surql { statements, .. } => {
+                    db.query("BEGIN TRANSACTION;")
+                        .query(statements.clone())
+                        .query(record_statement)
+                        .bind(format!("filename{}", index), file_name)
+                        .bind(format!("number{}", index), migration_file.number)
+                        .bind(format!("checksum{}", index), &migration_file.checksum)
+                        .query("COMMIT TRANSACTION;")
+                        .await?
+                        .checked()?;
+                }
+                MigrationAction::Rust(rust_migration) => {
+                    let Some(images) = images else {
+                        warn!(file = %file_name, "rust migration is skipped without image manager");
+                        continue;
+                    };
+                    info!(file = %file_name, "applying rust migration...");
+                    rust_migration
+                        .migrate(db, images)
+                        .await
+                        .map_err(|err| anyhow!("rust migration {} failed: {:?}", file_name, err))?;
+                    db.query(record_statement)
+                        .bind(format!("filename{}", index), file_name)
+                        .bind(format!("number{}", index), migration_file.number)
+                        .bind(format!("checksum{}", index), &migration_file.checksum)
+                        .await?
+                        .checked()?;
+                }
+            }
             migrated_count += 1;
-            db.query("BEGIN TRANSACTION;")
-                .query(migration_file.statements.clone())
+        }
+
+        check_schema_version(db, true).await?;
+
+        Ok(migrated_count)
+    }
+
+    /// Reports which migrations have been applied, which are pending and which drifted.
+    pub async fn plan(&self, db: &DatabaseRootAccess) -> anyhow::Result<MigrationPlan> {
+        // A missing migration table means no migration has been applied yet
+        let applied: Vec<String> = db
+            .query("SELECT VALUE file_name FROM migration ORDER BY number;")
+            .await?
+            .take(0)
+            .unwrap_or_default();
+
+        Ok(MigrationPlan {
+            applied,
+            pending: self.pending_migrations(db).await?,
+            drifted: self.drifts(db).await?,
+            schema_version: schema_version(db).await?.map(|version| version.to_string()),
+        })
+    }
+
+    /// Applies the pending migrations to an in-memory copy of the database, so they can be
+    /// validated without touching the actual data. Returns the file names of the migrations
+    /// which were applied to the copy.
+    pub async fn dry_run_migrations(&self, db: &DatabaseRootAccess) -> anyhow::Result<Vec<String>> {
+        let pending = self.pending_migrations(db).await?;
+        let copy = db.in_memory_copy().await?;
+        // Drift is reported by the plan, it must not prevent validating the pending migrations
+        // Rust migrations are skipped, as the image manager works on the actual data
+        self.apply_migrations(&copy, None, MigrationDriftPolicy::Warn)
+            .await?;
+        Ok(pending)
+    }
+
+    /// Compares the applied migrations with the migration files. Migrations applied before
+    /// checksums were recorded are only checked for existence.
+    pub async fn drifts(&self, db: &DatabaseRootAccess) -> anyhow::Result<Vec<MigrationDrift>> {
+        // A missing migration table means no migration has been applied yet
+        let applied_migrations: Vec<AppliedMigration> = db
+            .query("SELECT file_name, checksum, number FROM migration ORDER BY number;")
+            .await?
+            .take(0)
+            .unwrap_or_default();
+
+        let mut drifts = Vec::new();
+        for applied_migration in applied_migrations {
+            let migration_file = self
+                .migrations
+                .iter()
+                .find(|migration_file| migration_file.file_name == applied_migration.file_name);
+            match (migration_file, applied_migration.checksum) {
+                (None, _) => drifts.push(MigrationDrift::Missing {
+                    file_name: applied_migration.file_name,
+                }),
+                (Some(migration_file), Some(applied_checksum))
+                    if applied_checksum != migration_file.checksum =>
+                {
+                    drifts.push(MigrationDrift::Modified {
+                        file_name: applied_migration.file_name,
+                        applied_checksum,
+                        file_checksum: migration_file.checksum.clone(),
+                    })
+                }
+                _ => {}
+            }
+        }
+
+        Ok(drifts)
+    }
+
+    /// Reverts the last `count` applied migrations, newest first, using their `.down.surql` files.
+    /// All migrations are reverted in a single transaction, so either all or none of them are.
+    /// Returns the file names of the reverted migrations.
+    pub async fn rollback_migrations(
+        &self,
+        db: &DatabaseRootAccess,
+        count: u32,
+    ) -> anyhow::Result<Vec<String>> {
+        let applied_file_names: Vec<String> = db
+            .query("SELECT VALUE file_name FROM migration ORDER BY number DESC LIMIT $count;")
+            .bind("count", count)
+            .await?
+            .take(0)?;
+        if applied_file_names.len() < count as usize {
+            bail!(
+                "unable to rollback {} migrations, only {} have been applied",
+                count,
+                applied_file_names.len()
+            );
+        }
+
+        let mut query = db.query("BEGIN TRANSACTION;");
+        for (index, file_name) in applied_file_names.iter().enumerate() {
+            let migration_file = self
+                .migrations
+                .iter()
+                .find(|migration_file| &migration_file.file_name == file_name)
+                .ok_or(anyhow!(
+                    "applied migration {} does not exist on file system",
+                    file_name
+                ))?;
+            let MigrationAction::Surql {
+                down_statements: Some(down_statements),
+                ..
+            } = &migration_file.action
+            else {
+                bail!("migration {} has no `.down.surql` file", file_name);
+            };
+
+            query = query
+                .query(down_statements.clone())
                 .query(format!(
-                    "CREATE migration SET file_name = $filename{0}, number = $number{0};",
+                    "DELETE migration WHERE file_name = $filename{};",
                     index
                 ))
-                .bind(format!("filename{}", index), file_name)
-                .bind(format!("number{}", index), migration_file.number)
-                .query("COMMIT TRANSACTION;")
+                .bind(format!("filename{}", index), file_name);
+        }
+        query.query("COMMIT TRANSACTION;").await?.checked()?;
+
+        for file_name in &applied_file_names {
+            info!(file = %file_name, "rolled back migration");
+        }
+        Ok(applied_file_names)
+    }
+
+    /// Returns the file names of all migrations which have not been applied to the database yet.
+    pub async fn pending_migrations(&self, db: &DatabaseRootAccess) -> anyhow::Result<Vec<String>> {
+        let mut pending = Vec::new();
+        for migration_file in &self.migrations {
+            // A missing migration table means no migration has been applied yet
+            let applied_number: Option<usize> = db
+                .query("SELECT VALUE number FROM ONLY migration WHERE file_name = $migration_file_name LIMIT 1;")
+                .bind("migration_file_name", &migration_file.file_name)
                 .await?
-                .checked()?;
+                .take(0)
+                .unwrap_or(None);
+            if applied_number.is_none() {
+                pending.push(migration_file.file_name.clone());
+            }
         }
 
-        Ok(migrated_count)
+        Ok(pending)
     }
 }
 
+const MIGRATION_TABLE: &str = r#"
+    DEFINE TABLE migration SCHEMAFULL PERMISSIONS NONE;
+
+    DEFINE FIELD file_name ON TABLE migration TYPE string;
+    DEFINE FIELD number ON TABLE migration TYPE int;
+    DEFINE FIELD checksum ON TABLE migration TYPE option<string>;
+    DEFINE FIELD exec_time ON TABLE migration TYPE datetime DEFAULT time::now();
+"#;
+
 async fn load_schema_file(folder: impl Into<PathBuf>) -> anyhow::Result<SchemaFile> {
     let folder = folder.into();
     let schema_file = folder.join("schema.surql");
@@ -147,7 +434,7 @@ async fn load_schema_file(folder: impl Into<PathBuf>) -> anyhow::Result<SchemaFi
     })
 }
 
-async fn load_migration_files(path: impl Into<PathBuf>) -> anyhow::Result<Vec<MigrationFile>> {
+async fn load_migration_files(path: impl Into<PathBuf>) -> anyhow::Result<Vec<Migration>> {
     let folder = path.into();
     let mut migration_files = Vec::new();
 
@@ -181,6 +468,11 @@ async fn load_migration_files(path: impl Into<PathBuf>) -> anyhow::Result<Vec<Mi
             continue;
         }
 
+        // Down migrations are loaded together with their migration
+        if file_name.ends_with(".down.surql") {
+            continue;
+        }
+
         if &file_extension != "surql" {
             warn!(
                 "migration file {} is no valid SurrealQL file (requires `.surql` extension)",
@@ -199,22 +491,26 @@ async fn load_migration_files(path: impl Into<PathBuf>) -> anyhow::Result<Vec<Mi
         };
         let number = usize::from_str(number_match.as_str())?;
 
-        let surql_file = load_surql_file(file_path).await?;
+        let surql_file = load_surql_file(&file_path).await?;
+        let down_file_path = file_path.with_file_name(format!(
+            "{}.down.surql",
+            file_name.trim_end_matches(".surql")
+        ));
+        let down_statements = if down_file_path.is_file() {
+            Some(load_surql_file(down_file_path).await?.statements)
+        } else {
+            None
+        };
 
-        migration_files.push(MigrationFile {
-            statements: surql_file.statements,
+        migration_files.push(Migration {
+            checksum: hex::encode(Sha256::digest(surql_file.content.as_bytes())),
+            action: MigrationAction::Surql {
+                statements: surql_file.statements,
+                down_statements,
+            },
             file_name,
             number,
         });
     }
-    migration_files.sort_by_key(|migration| migration.number);
-
-    // Checking migration files to ensure numerical consistency
-    for (index, file) in migration_files.iter().enumerate() {
-        if file.number != index + 1 {
-            bail!("migration files do not start at 1 or are more than one apart");
-        }
-    }
-
     Ok(migration_files)
 }
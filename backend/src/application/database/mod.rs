@@ -1,9 +1,15 @@
+pub use engine::DatabaseEngine;
 pub use file::load_surql_file;
 pub use init::create_database_system;
+pub use init::open_database_system;
+pub use migration::{MigrationDrift, MigrationDriftPolicy, MigrationManager, MigrationPlan};
 pub use system::DatabaseRootAccess;
 pub use system::DatabaseSystem;
 
+mod engine;
 mod file;
 mod init;
 mod migration;
+mod rust_migrations;
 pub mod system;
+mod version;
@@ -0,0 +1,26 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::application::database::DatabaseRootAccess;
+use crate::application::ImageManager;
+
+/// A migration implemented in Rust for transformations SurrealQL is unable to express, e.g.
+/// re-encoding stored images. Rust migrations share their numbers with the migration files and
+/// are applied in between them.
+#[async_trait]
+pub trait RustMigration: Send + Sync {
+    /// Position among the migration files, has to be unique.
+    fn number(&self) -> usize;
+
+    fn name(&self) -> &str;
+
+    /// Rust migrations are not executed inside a transaction. They are recorded after they
+    /// finished, so they have to tolerate being executed again if the application stops midway.
+    async fn migrate(&self, db: &DatabaseRootAccess, images: &ImageManager) -> anyhow::Result<()>;
+}
+
+/// All migrations implemented in Rust, new migrations have to be registered here.
+pub fn rust_migrations() -> Vec<Arc<dyn RustMigration>> {
+    Vec::new()
+}
@@ -54,8 +54,23 @@ impl DatabaseSystem {
         &self,
         token: impl Into<AuthToken>,
     ) -> anyhow::Result<DatabaseScopeAccess> {
+        let token = token.into();
         let mut session = Session::default();
-        iam::verify::token(&self.datastore, &mut session, token.into().as_insecure()).await?;
+        iam::verify::token(&self.datastore, &mut session, token.as_insecure()).await?;
+
+        // Changing the password bumps the session generation, which revokes every earlier session
+        let is_current = self
+            .auth_root()
+            .query(
+                "RETURN (SELECT VALUE generation = user.session_generation FROM ONLY session WHERE token_hash = crypto::sha256($token) AND user = $user LIMIT 1) ?? false;",
+            )
+            .bind("token", token.as_insecure())
+            .bind("user", &session.rd)
+            .await?
+            .take::<Option<bool>>(0)?;
+        if is_current != Some(true) {
+            bail!("token was revoked by a password change");
+        }
 
         Ok(DatabaseAccess {
             datastore: Arc::clone(&self.datastore),
@@ -63,6 +78,21 @@ impl DatabaseSystem {
         })
     }
 
+    /// Records the session of a newly issued token with the session generation of its user,
+    /// tokens are only accepted by [DatabaseSystem::auth_token] while that generation is current.
+    async fn record_session(&self, token: &AuthToken, generation: i64) -> anyhow::Result<()> {
+        let mut session = Session::default();
+        iam::verify::token(&self.datastore, &mut session, token.as_insecure()).await?;
+        self.auth_root()
+            .query("CREATE session SET token_hash = crypto::sha256($token), user = $user, generation = $generation;")
+            .bind("token", token.as_insecure())
+            .bind("user", &session.rd)
+            .bind("generation", generation)
+            .await?
+            .checked()?;
+        Ok(())
+    }
+
     pub async fn signin(&self, scope: &str, vars: impl Serialize) -> anyhow::Result<AuthToken> {
         let value = to_value(vars)?;
         let Value::Object(mut vars) = value else {
@@ -72,6 +102,14 @@ impl DatabaseSystem {
             );
             bail!("provided vars in database signin were not an object")
         };
+        // Read before the password is checked, so a concurrent reset revokes this session as well
+        let generation = self
+            .auth_root()
+            .query("RETURN (SELECT VALUE session_generation FROM ONLY user WHERE email = $email LIMIT 1) ?? 0;")
+            .bind("email", vars.get("email"))
+            .await?
+            .take::<Option<i64>>(0)?
+            .unwrap_or(0);
         let maybe_token = surrealdb_core::iam::signin::sc(
             &self.datastore,
             &mut Session::default(),
@@ -81,10 +119,12 @@ impl DatabaseSystem {
             vars,
         )
         .await?;
-        match maybe_token {
-            None => bail!("no token generated"),
-            Some(token) => Ok(token.into()),
-        }
+        let Some(token) = maybe_token else {
+            bail!("no token generated");
+        };
+        let token = AuthToken::from(token);
+        self.record_session(&token, generation).await?;
+        Ok(token)
     }
 
     pub async fn signup(&self, scope: &str, vars: impl Serialize) -> anyhow::Result<AuthToken> {
@@ -105,10 +145,13 @@ impl DatabaseSystem {
             vars,
         )
         .await?;
-        match maybe_token {
-            None => bail!("no token generated"),
-            Some(token) => Ok(token.into()),
-        }
+        let Some(token) = maybe_token else {
+            bail!("no token generated");
+        };
+        // New users start at the first session generation
+        let token = AuthToken::from(token);
+        self.record_session(&token, 0).await?;
+        Ok(token)
     }
 }
 
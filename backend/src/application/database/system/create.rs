@@ -1,19 +1,46 @@
 use std::sync::Arc;
 
+use anyhow::anyhow;
 use surrealdb_core::dbs::Session;
 use surrealdb_core::iam::signin;
 use surrealdb_core::kvs::Datastore;
 use tracing::error;
 
-use crate::application::DatabaseSystem;
+use crate::application::{DatabaseEngine, DatabaseSystem};
 
 impl DatabaseSystem {
-    pub async fn create(ns: &str, db: &str, user: &str, password: &str) -> anyhow::Result<Self> {
-        let datastore = if cfg!(feature = "prod-env") {
-            Datastore::new("speedb://data/").await?
-        } else {
-            Datastore::new("memory").await?
-        };
+    pub async fn create(
+        engine: &DatabaseEngine,
+        ns: &str,
+        db: &str,
+        user: &str,
+        password: &str,
+    ) -> anyhow::Result<Self> {
+        let address = engine.address();
+        let datastore = Datastore::new(&address).await.map_err(|err| {
+            anyhow!("unable to open datastore `{address}`, the storage engine may not be compiled in: {err}")
+        })?;
+        Self::with_datastore(datastore, ns, db, user, password).await
+    }
+
+    /// Creates a throwaway database system which only resides in memory.
+    pub async fn create_in_memory(
+        ns: &str,
+        db: &str,
+        user: &str,
+        password: &str,
+    ) -> anyhow::Result<Self> {
+        let datastore = Datastore::new("memory").await?;
+        Self::with_datastore(datastore, ns, db, user, password).await
+    }
+
+    async fn with_datastore(
+        datastore: Datastore,
+        ns: &str,
+        db: &str,
+        user: &str,
+        password: &str,
+    ) -> anyhow::Result<Self> {
         let datastore = datastore
             .with_strict_mode(true)
             .with_auth_enabled(true)
@@ -37,3 +64,23 @@ impl DatabaseSystem {
         Ok(Self::new(datastore, db_session))
     }
 }
+
+#[cfg(test)]
+impl DatabaseSystem {
+    /// Creates an in-memory database system with the schema and all migrations of the working
+    /// directory applied, Rust migrations are skipped.
+    pub async fn create_migrated_in_memory() -> anyhow::Result<Self> {
+        use crate::application::database::{MigrationDriftPolicy, MigrationManager};
+
+        let system = Self::create_in_memory("test", "test", "test", "test").await?;
+        let migrations = MigrationManager::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../working_dir/migrations"
+        ))
+        .await?;
+        migrations
+            .apply_migrations(&system.auth_root(), None, MigrationDriftPolicy::Warn)
+            .await?;
+        Ok(system)
+    }
+}
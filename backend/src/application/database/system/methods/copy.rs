@@ -0,0 +1,37 @@
+use tokio::io::AsyncReadExt;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::application::database::system::IntoImport;
+use crate::application::database::DatabaseRootAccess;
+use crate::application::DatabaseSystem;
+
+impl DatabaseRootAccess {
+    /// Copies this database into a throwaway in-memory datastore, so statements can be tried out
+    /// without affecting the actual data.
+    pub async fn in_memory_copy(&self) -> anyhow::Result<DatabaseRootAccess> {
+        info!("copying database into memory");
+        let export = self.export().await?;
+        tokio::pin!(export);
+        let mut content = String::new();
+        export.read_to_string(&mut content).await?;
+
+        DatabaseSystem::in_memory_import(content).await
+    }
+}
+
+impl DatabaseSystem {
+    /// Imports an export into a new throwaway in-memory datastore, e.g. to test-restore backups.
+    pub async fn in_memory_import(export: impl IntoImport) -> anyhow::Result<DatabaseRootAccess> {
+        let memory_db = DatabaseSystem::create_in_memory(
+            "memory",
+            "memory",
+            "memory",
+            &Uuid::now_v7().simple().to_string(),
+        )
+        .await?
+        .auth_root();
+        memory_db.import(export).await?;
+        Ok(memory_db)
+    }
+}
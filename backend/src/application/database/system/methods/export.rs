@@ -1,9 +1,13 @@
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, VecDeque};
 use std::io::{BufReader, BufWriter, Read, Write};
 use std::{io, thread};
 
 use futures_lite::{Stream, StreamExt};
-use tokio::io::{AsyncBufRead, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use surrealdb_core::sql::statements::{RelateStatement, UpdateStatement};
+use surrealdb_core::sql::{parse, Query, Statement, Value};
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt,
+};
 use tokio::sync::mpsc::{Sender, UnboundedSender};
 use tokio_util::io::StreamReader;
 use tracing::{error, info};
@@ -27,3 +31,94 @@ impl DatabaseRootAccess {
         })))
     }
 }
+
+/// Copies a database export into `target` line by line and returns the amount of records per
+/// table it contains, so the export never has to be held in memory as a whole.
+pub async fn copy_export_counting_records(
+    export: impl AsyncBufRead,
+    target: &mut (impl AsyncWrite + Unpin),
+) -> anyhow::Result<BTreeMap<String, u64>> {
+    tokio::pin!(export);
+    let mut counter = ExportRecordCounter::default();
+    let mut line = String::new();
+    while export.read_line(&mut line).await? > 0 {
+        target.write_all(line.as_bytes()).await?;
+        counter.push_line(&line);
+        line.clear();
+    }
+    target.flush().await?;
+    counter.finish()
+}
+
+/// Counts records statement by statement. Exports write every record as its own `UPDATE`
+/// statement, or `RELATE` statement for graph edges.
+#[derive(Default)]
+struct ExportRecordCounter {
+    statement: String,
+    record_counts: BTreeMap<String, u64>,
+}
+
+impl ExportRecordCounter {
+    fn push_line(&mut self, line: &str) {
+        self.statement.push_str(line);
+        // Strings may span multiple lines, so a line ending in `;` does not always end a statement
+        if !self.statement.trim_end().ends_with(';') {
+            return;
+        }
+        if let Ok(query) = parse(&self.statement) {
+            self.count(query);
+            self.statement.clear();
+        }
+    }
+
+    /// Fails if the export ends within a statement.
+    fn finish(mut self) -> anyhow::Result<BTreeMap<String, u64>> {
+        if !self.statement.trim().is_empty() {
+            let query = parse(&self.statement)?;
+            self.count(query);
+        }
+        Ok(self.record_counts)
+    }
+
+    fn count(&mut self, query: Query) {
+        for statement in query.0 {
+            let records = match statement {
+                Statement::Update(UpdateStatement { what, .. }) => what.0,
+                Statement::Relate(RelateStatement { kind, .. }) => vec![kind],
+                _ => continue,
+            };
+            for record in records {
+                if let Value::Thing(thing) = record {
+                    *self.record_counts.entry(thing.tb).or_insert(0) += 1;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn counts_records_while_copying() {
+        let export = "-- TABLE DATA: user\n\nBEGIN TRANSACTION;\n\nUPDATE user:a CONTENT { id: user:a, bio: 'first;\nsecond' };\nUPDATE user:b CONTENT { id: user:b };\n\nCOMMIT TRANSACTION;\n\nRELATE user:a -> follows:c -> user:b CONTENT { id: follows:c, in: user:a, out: user:b };\n";
+        let mut copy = Vec::new();
+        let record_counts = copy_export_counting_records(export.as_bytes(), &mut copy)
+            .await
+            .unwrap();
+
+        assert_eq!(copy, export.as_bytes());
+        assert_eq!(
+            record_counts,
+            BTreeMap::from([("follows".to_string(), 1), ("user".to_string(), 2)])
+        );
+    }
+
+    #[tokio::test]
+    async fn truncated_export_fails() {
+        let export = "UPDATE user:a CONTENT { id: user:a, bio: 'first;\n";
+        let result = copy_export_counting_records(export.as_bytes(), &mut tokio::io::sink()).await;
+        assert!(result.is_err());
+    }
+}
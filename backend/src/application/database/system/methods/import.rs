@@ -1,19 +1,40 @@
 use std::io::{BufReader, Read};
 
+use surrealdb_core::sql::{parse, Statement, Table};
 use tokio::io::{AsyncRead, AsyncReadExt};
 use tracing::{error, info};
 
-use crate::application::database::system::access::Auth;
+use crate::application::database::system::opts::IntoStatements;
 use crate::application::database::DatabaseRootAccess;
 
 impl DatabaseRootAccess {
+    /// Replaces the content of this database with the given export.
+    /// The import is executed as a single transaction, so either the whole export is applied
+    /// or the database is left untouched.
     pub async fn import(&self, import: impl IntoImport) -> anyhow::Result<()> {
         info!("attempting database import");
         let result: anyhow::Result<()> = try {
             let content = import.into_import().await?;
-            self.datastore
-                .import(&content, self.auth.as_session())
-                .await?;
+            // Exports wrap every table in its own transaction, which would break atomicity
+            let statements = parse(&content)?
+                .into_statements()?
+                .into_iter()
+                .filter(|statement| {
+                    !matches!(statement, Statement::Begin(_) | Statement::Commit(_))
+                })
+                .collect::<Vec<_>>();
+
+            let tables = self.tables().await?;
+
+            let mut query = self.query("BEGIN TRANSACTION;");
+            for table in tables {
+                query = query.query(format!("REMOVE TABLE {};", Table(table)));
+            }
+            query
+                .query(statements)
+                .query("COMMIT TRANSACTION;")
+                .await?
+                .checked()?;
         };
         result.inspect_err(|err| error!(?err, "database import failed due to error"))?;
         Ok(())
@@ -0,0 +1,43 @@
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, bail};
+use surrealdb_core::sql::{Object, Value};
+
+use crate::application::database::DatabaseRootAccess;
+use crate::utils::types::GenericTryInto;
+
+impl DatabaseRootAccess {
+    /// Names of all tables defined in this database.
+    pub async fn tables(&self) -> anyhow::Result<Vec<String>> {
+        let db_info: Value = self
+            .query("INFO FOR DB")
+            .await?
+            .take(0)
+            .map_err(|_| anyhow!("no info for db"))?;
+        let Ok(mut db_info) = db_info.try_into_type::<Object>() else {
+            bail!("info for db is not an object");
+        };
+        let tables = db_info
+            .remove("tables")
+            .ok_or(anyhow!("no tables inside info for db"))?
+            .try_into_type::<Object>()
+            .map_err(|_| anyhow!("tables inside info for db is not an object"))?;
+
+        Ok(tables.keys().cloned().collect())
+    }
+
+    /// Amount of records inside every table of this database.
+    pub async fn record_counts(&self) -> anyhow::Result<BTreeMap<String, u64>> {
+        let mut record_counts = BTreeMap::new();
+        for table in self.tables().await? {
+            let count: Option<u64> = self
+                .query("RETURN count(SELECT VALUE id FROM type::table($table));")
+                .bind("table", &table)
+                .await?
+                .take(0)?;
+            record_counts.insert(table, count.unwrap_or(0));
+        }
+
+        Ok(record_counts)
+    }
+}
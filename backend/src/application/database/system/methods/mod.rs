@@ -1,5 +1,10 @@
+pub use export::copy_export_counting_records;
+pub use import::IntoImport;
+
 mod backup;
+mod copy;
 mod export;
 mod health;
 mod import;
+mod info;
 mod query;
@@ -8,6 +8,7 @@ use tracing::{error, warn};
 pub use access::AuthToken;
 pub use access::DatabaseRootAccess;
 pub use access::DatabaseScopeAccess;
+pub use methods::{copy_export_counting_records, IntoImport};
 pub use opts::{Response, Responses};
 
 use crate::application::database::system::access::{DatabaseAccess, RootAuth, ScopeAuth};
@@ -54,6 +54,11 @@ impl Responses {
             .flatten()
     }
 
+    /// Statistics of every response in order
+    pub fn stats(&self) -> Vec<Stats> {
+        self.0.iter().map(|response| response.to_stats()).collect()
+    }
+
     pub fn collect(self) -> Vec<Response> {
         self.0
             .into_iter()
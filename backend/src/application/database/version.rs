@@ -0,0 +1,108 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail};
+use tracing::{debug, warn};
+
+use crate::application::database::DatabaseRootAccess;
+
+/// Oldest database schema this binary is able to work with, older schemas have to be migrated.
+pub const MIN_SCHEMA_VERSION: SchemaVersion = SchemaVersion(0, 0, 1);
+/// Newest database schema this binary is able to work with. Has to be raised together with
+/// migrations bumping `$SCHEMA_VERSION`.
+pub const MAX_SCHEMA_VERSION: SchemaVersion = SchemaVersion(0, 6, 0);
+
+/// Version of the database schema as stored in the `$SCHEMA_VERSION` param,
+/// formatted as `major.minor.patch`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SchemaVersion(pub u32, pub u32, pub u32);
+
+impl FromStr for SchemaVersion {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts = s
+            .split('.')
+            .map(u32::from_str)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| anyhow!("schema version `{s}` is no valid version: {err}"))?;
+        let [major, minor, patch] = parts[..] else {
+            bail!("schema version `{s}` does not consist of major, minor and patch version");
+        };
+        Ok(SchemaVersion(major, minor, patch))
+    }
+}
+
+impl Display for SchemaVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.0, self.1, self.2)
+    }
+}
+
+/// Reads the `$SCHEMA_VERSION` param of the database, [None] if it is not defined.
+pub async fn schema_version(db: &DatabaseRootAccess) -> anyhow::Result<Option<SchemaVersion>> {
+    let version: Option<String> = db.query("RETURN $SCHEMA_VERSION;").await?.take(0)?;
+    version.as_deref().map(SchemaVersion::from_str).transpose()
+}
+
+/// Fails if the database schema is newer than this binary supports, e.g. because an older build
+/// is started against upgraded data. Schemas older than supported are only rejected if
+/// `require_minimum` is set, as they may still be migrated.
+pub async fn check_schema_version(
+    db: &DatabaseRootAccess,
+    require_minimum: bool,
+) -> anyhow::Result<()> {
+    let Some(version) = schema_version(db).await? else {
+        warn!("database defines no `$SCHEMA_VERSION`, unable to check schema compatibility");
+        return Ok(());
+    };
+
+    if version > MAX_SCHEMA_VERSION {
+        bail!(
+            "database schema version {version} is newer than the newest version {MAX_SCHEMA_VERSION} supported by this build, refusing to run an outdated build against upgraded data"
+        );
+    }
+    if require_minimum && version < MIN_SCHEMA_VERSION {
+        bail!(
+            "database schema version {version} is older than the oldest version {MIN_SCHEMA_VERSION} supported by this build, a migration is missing"
+        );
+    }
+
+    debug!(%version, "database schema version is supported");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_versions() {
+        assert_eq!(
+            SchemaVersion::from_str("0.4.0").unwrap(),
+            SchemaVersion(0, 4, 0)
+        );
+        assert_eq!(
+            SchemaVersion::from_str("12.0.3").unwrap(),
+            SchemaVersion(12, 0, 3)
+        );
+        assert_eq!(SchemaVersion(1, 2, 3).to_string(), "1.2.3");
+    }
+
+    #[test]
+    fn rejects_invalid_versions() {
+        for version in ["", "1", "1.2", "1.2.3.4", "1.x.3", "-1.0.0", "v1.0.0"] {
+            assert!(
+                SchemaVersion::from_str(version).is_err(),
+                "`{version}` was accepted"
+            );
+        }
+    }
+
+    #[test]
+    fn orders_by_components() {
+        assert!(SchemaVersion(0, 10, 0) > SchemaVersion(0, 9, 9));
+        assert!(SchemaVersion(1, 0, 0) > SchemaVersion(0, 99, 99));
+        assert!(MIN_SCHEMA_VERSION <= MAX_SCHEMA_VERSION);
+    }
+}
@@ -6,7 +6,7 @@ use reqwest::header::HeaderMap;
 use reqwest::Client;
 use serde_json::json;
 use surrealdb_core::sql::Value;
-use tokio::sync::{Mutex, Semaphore};
+use tokio::sync::{Mutex, RwLock, Semaphore};
 use tracing::{error, info, instrument, warn};
 
 use crate::application::database::DatabaseRootAccess;
@@ -19,8 +19,10 @@ use crate::secrets::MycologSecrets;
 pub struct EmailManager {
     db: DatabaseRootAccess,
     sender: String,
-    emails: BTreeMap<String, EmailFile>,
+    emails: RwLock<BTreeMap<String, EmailFile>>,
     client: Client,
+    /// Sending is disabled if no mailersend api key is configured.
+    sending_enabled: bool,
     guard: Mutex<()>,
 }
 
@@ -31,19 +33,21 @@ impl EmailManager {
         sender: impl Into<String>,
         emails: BTreeMap<String, EmailFile>,
     ) -> Self {
+        let api_key = secrets.keys.mailersend_api();
         let mut headers = HeaderMap::new();
-        headers.insert(
-            "Authorization",
-            format!("Bearer {}", secrets.keys.mailersend_api())
-                .parse()
-                .unwrap(),
-        );
+        if let Some(api_key) = &api_key {
+            headers.insert(
+                "Authorization",
+                format!("Bearer {api_key}").parse().unwrap(),
+            );
+        }
 
         Self {
             db,
             sender: sender.into(),
-            emails,
+            emails: RwLock::new(emails),
             client: Client::builder().default_headers(headers).build().unwrap(),
+            sending_enabled: api_key.is_some(),
             guard: Mutex::new(()),
         }
     }
@@ -97,10 +101,22 @@ impl EmailManager {
         subject: &str,
         recipients: Vec<Recipient>,
     ) -> anyhow::Result<()> {
-        let email_file = self.emails.get(email_type).ok_or(anyhow!(
-            "email type `{}` not found in loaded files",
-            email_type
-        ))?;
+        if !self.sending_enabled {
+            bail!(
+                "unable to send email type `{}`, sending emails is disabled because no mailersend api key is configured",
+                email_type
+            );
+        }
+        let email_file = self
+            .emails
+            .read()
+            .await
+            .get(email_type)
+            .cloned()
+            .ok_or(anyhow!(
+                "email type `{}` not found in loaded files",
+                email_type
+            ))?;
         if email_file.text.is_none() && email_file.html.is_none() {
             bail!(
                 "email type `{}` has neither text or html content",
@@ -133,6 +149,11 @@ impl EmailManager {
         Ok(())
     }
 
+    /// Replaces the loaded email templates, e.g. after they changed on disk.
+    pub async fn replace_emails(&self, emails: BTreeMap<String, EmailFile>) {
+        *self.emails.write().await = emails;
+    }
+
     async fn persist_email(
         &self,
         email_type: &str,
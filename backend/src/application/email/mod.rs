@@ -1,7 +1,7 @@
+pub use files::load_email_files;
 pub use manager::EmailManager;
 pub use recipients::Recipient;
 
-use crate::application::email::files::load_email_files;
 use crate::application::DatabaseSystem;
 use crate::config::MycologConfig;
 use crate::secrets::MycologSecrets;
@@ -16,7 +16,7 @@ pub async fn create_email_manager(
     secrets: &MycologSecrets,
     db: &DatabaseSystem,
 ) -> anyhow::Result<EmailManager> {
-    let emails = load_email_files("emails/").await?;
+    let emails = load_email_files(&config.emails_dir).await?;
     Ok(EmailManager::new(
         secrets,
         db.auth_root(),
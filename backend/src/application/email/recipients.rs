@@ -1,6 +1,8 @@
 use std::collections::BTreeMap;
 
-#[derive(Clone)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Recipient {
     pub email: String,
     pub variables: BTreeMap<String, String>,
@@ -17,7 +17,7 @@ pub async fn create_image_manager(
     db: &DatabaseSystem,
 ) -> anyhow::Result<ImageManager> {
     let db = db.auth_root();
-    let manager = ImageManager::new("images/", db, config.images_max_bytes_per_user)?;
+    let manager = ImageManager::new(&config.images_dir, db, config.images_max_bytes_per_user)?;
     info!("cleaning image manager during creation");
     manager.clean().await?;
     manager.constrain_images().await?;
@@ -10,7 +10,7 @@ mod service;
 pub async fn logging_task(context: Arc<MycologContext>) {
     let shutdown_token = context.task_cancel_token.clone();
 
-    if let Err(err) = logging_service(shutdown_token).await {
+    if let Err(err) = logging_service(&context.config.logs_dir, shutdown_token).await {
         error!(?err, "logging service stopped working due to error")
     }
     info!("stopped logging service");
@@ -1,4 +1,5 @@
 use std::io::BufWriter;
+use std::path::Path;
 use std::time::Duration;
 
 use async_compression::tokio::write::BrotliEncoder;
@@ -11,7 +12,10 @@ use tracing::{debug, error, info, info_span, instrument};
 use crate::application::database::DatabaseRootAccess;
 use crate::application::ScheduleQueries;
 
-pub async fn logging_service(shutdown_token: CancellationToken) -> anyhow::Result<()> {
+pub async fn logging_service(
+    logs_dir: &Path,
+    shutdown_token: CancellationToken,
+) -> anyhow::Result<()> {
     let mut daily_timer = interval_at(
         Instant::now() + Duration::from_mins(1),
         Duration::from_days(1),
@@ -21,7 +25,7 @@ pub async fn logging_service(shutdown_token: CancellationToken) -> anyhow::Resul
     while !shutdown_token.is_cancelled() {
         tokio::select!(
             _ = shutdown_token.cancelled() => break,
-            _ = daily_timer.tick() => compress_log_files().await.inspect_err(|err| error!(?err, "compressing log files failed")),
+            _ = daily_timer.tick() => compress_log_files(logs_dir).await.inspect_err(|err| error!(?err, "compressing log files failed")),
         );
     }
 
@@ -29,10 +33,10 @@ pub async fn logging_service(shutdown_token: CancellationToken) -> anyhow::Resul
 }
 
 #[instrument]
-async fn compress_log_files() -> anyhow::Result<()> {
+async fn compress_log_files(logs_dir: &Path) -> anyhow::Result<()> {
     let mut compressable_files = Vec::new();
 
-    let mut dir = tokio::fs::read_dir("logs/").await?;
+    let mut dir = tokio::fs::read_dir(logs_dir).await?;
     while let Some(entry) = dir.next_entry().await? {
         let path = entry.path();
         let Some(extension) = path
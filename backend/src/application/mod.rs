@@ -9,18 +9,35 @@ use tracing_log::log::info;
 
 use backups::backup_task;
 pub use backups::BackupLimit;
+pub use backups::{
+    recent_backup_verifications, restore_backup_archive, write_backup_archive, BackupVerification,
+};
+pub use backups::{BackupTargetConfig, BackupTargetKind};
 pub use database::create_database_system;
+pub use database::open_database_system;
+pub use database::DatabaseEngine;
+pub use database::DatabaseRootAccess;
 pub use database::DatabaseSystem;
+pub use database::{MigrationDrift, MigrationDriftPolicy, MigrationManager, MigrationPlan};
 pub use email::create_email_manager;
 pub use email::EmailManager;
 pub use images::create_image_manager;
 pub use images::ImageManager;
+pub use queue::create_job_queue;
+pub use queue::{Job, JobHandler, JobQueue, JobStatus, SendEmailPayload};
 pub use schedules::load_schedule_queries;
 pub use schedules::ScheduleQueries;
+pub use schedules::{create_job_registry, JobRegistry, ScheduledJob};
+pub use schedules::{
+    recent_schedule_runs, run_schedule_now, Schedule, ScheduleAction, ScheduleRun,
+};
 
 use crate::application::logging::logging_task;
+use crate::application::queue::queue_task;
 use crate::application::schedules::schedule_task;
+use crate::application::secrets::secrets_reload_task;
 use crate::application::signals::exit_signal;
+use crate::application::watch::watch_task;
 use crate::application::web::web_server_task;
 use crate::context::MycologContext;
 use crate::utils::asynchronous::run_catch;
@@ -30,8 +47,11 @@ mod database;
 mod email;
 mod images;
 mod logging;
+mod queue;
 mod schedules;
+mod secrets;
 mod signals;
+mod watch;
 mod web;
 
 pub async fn run_application(state: &Arc<MycologContext>) -> i32 {
@@ -64,6 +84,14 @@ pub async fn try_start_application(context: Arc<MycologContext>) -> anyhow::Resu
     debug!("tracking web server service");
     tasks.spawn(logging_task(Arc::clone(&context)));
     debug!("tracking logging service");
+    tasks.spawn(watch_task(Arc::clone(&context)));
+    debug!("tracking file watch service");
+    tasks.spawn(secrets_reload_task(Arc::clone(&context)));
+    debug!("tracking secrets reload service");
+    for worker in 0..context.config.queue_workers {
+        tasks.spawn(queue_task(Arc::clone(&context), worker));
+    }
+    debug!("tracking job queue workers");
 
     tasks.close();
     Ok(())
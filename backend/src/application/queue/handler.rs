@@ -0,0 +1,79 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::application::email::events::EmailWebhookEvent;
+use crate::application::email::Recipient;
+use crate::context::MycologContext;
+
+/// Processes the payload of all jobs of one kind.
+#[async_trait]
+pub trait JobHandler: Send + Sync {
+    fn kind(&self) -> &str;
+
+    /// Failed jobs are retried, handlers therefore have to tolerate being executed more than once.
+    async fn handle(
+        &self,
+        context: &MycologContext,
+        payload: serde_json::Value,
+    ) -> anyhow::Result<()>;
+}
+
+pub fn default_job_handlers() -> BTreeMap<String, Arc<dyn JobHandler>> {
+    let handlers: [Arc<dyn JobHandler>; 2] =
+        [Arc::new(EmailWebhookHandler), Arc::new(SendEmailHandler)];
+    handlers
+        .into_iter()
+        .map(|handler| (handler.kind().to_string(), handler))
+        .collect()
+}
+
+/// Applies a MailerSend webhook event to the stored email status.
+pub struct EmailWebhookHandler;
+
+#[async_trait]
+impl JobHandler for EmailWebhookHandler {
+    fn kind(&self) -> &str {
+        "email_webhook"
+    }
+
+    async fn handle(
+        &self,
+        context: &MycologContext,
+        payload: serde_json::Value,
+    ) -> anyhow::Result<()> {
+        let event = EmailWebhookEvent::try_from(payload)?;
+        context.email.process(event).await
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SendEmailPayload {
+    pub email_type: String,
+    pub subject: String,
+    pub recipients: Vec<Recipient>,
+}
+
+/// Submits an email using one of the loaded email templates.
+pub struct SendEmailHandler;
+
+#[async_trait]
+impl JobHandler for SendEmailHandler {
+    fn kind(&self) -> &str {
+        "send_email"
+    }
+
+    async fn handle(
+        &self,
+        context: &MycologContext,
+        payload: serde_json::Value,
+    ) -> anyhow::Result<()> {
+        let payload: SendEmailPayload = serde_json::from_value(payload)?;
+        context
+            .email
+            .sumbit_email(&payload.email_type, &payload.subject, payload.recipients)
+            .await
+    }
+}
@@ -0,0 +1,64 @@
+use std::str::FromStr;
+
+use anyhow::bail;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize, Serializer};
+use surrealdb_core::sql;
+
+/// A unit of work persisted in the `job` table.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Job {
+    #[serde(serialize_with = "serialize_id")]
+    pub id: sql::Thing,
+    pub kind: String,
+    pub payload: serde_json::Value,
+    pub status: JobStatus,
+    pub attempts: u32,
+    pub max_attempts: u32,
+    pub run_after: DateTime<Utc>,
+    pub locked_until: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub time_created: DateTime<Utc>,
+    pub time_finished: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    /// Waiting for its first or next attempt after `run_after`.
+    Pending,
+    /// Claimed by a worker until `locked_until`, after which it may be claimed again.
+    Running,
+    Done,
+    /// Failed `max_attempts` times and is not retried anymore.
+    Dead,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Pending => "pending",
+            JobStatus::Running => "running",
+            JobStatus::Done => "done",
+            JobStatus::Dead => "dead",
+        }
+    }
+}
+
+impl FromStr for JobStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "pending" => JobStatus::Pending,
+            "running" => JobStatus::Running,
+            "done" => JobStatus::Done,
+            "dead" => JobStatus::Dead,
+            _ => bail!("unknown job status `{s}`"),
+        })
+    }
+}
+
+fn serialize_id<S: Serializer>(id: &sql::Thing, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&id.to_raw())
+}
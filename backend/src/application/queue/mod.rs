@@ -0,0 +1,41 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tracing::{error, info};
+
+use crate::application::queue::handler::default_job_handlers;
+use crate::application::queue::service::queue_service;
+use crate::application::DatabaseSystem;
+use crate::config::MycologConfig;
+use crate::context::MycologContext;
+
+pub use handler::{JobHandler, SendEmailPayload};
+pub use job::{Job, JobStatus};
+pub use queue::JobQueue;
+
+mod handler;
+mod job;
+mod queue;
+mod service;
+
+pub fn create_job_queue(config: &MycologConfig, db: &DatabaseSystem) -> JobQueue {
+    JobQueue::new(
+        db.auth_root(),
+        default_job_handlers(),
+        config.queue_max_attempts,
+        Duration::from_secs(config.queue_visibility_timeout_secs),
+    )
+}
+
+pub async fn queue_task(context: Arc<MycologContext>, worker: u32) {
+    let shutdown_token = context.task_cancel_token.clone();
+
+    if let Err(err) = queue_service(&context, worker, shutdown_token).await {
+        error!(
+            worker,
+            ?err,
+            "job queue worker stopped working due to error"
+        );
+    }
+    info!(worker, "stopped job queue worker");
+}
@@ -0,0 +1,221 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::bail;
+use chrono::Utc;
+use serde::Serialize;
+use surrealdb_core::sql;
+use tokio::sync::{Mutex, Notify};
+use tracing::{debug, error, info, warn};
+
+use crate::application::database::DatabaseRootAccess;
+use crate::application::queue::handler::JobHandler;
+use crate::application::queue::job::{Job, JobStatus};
+
+/// Delay before the first retry of a failed job, doubled for every further attempt.
+const BASE_BACKOFF: Duration = Duration::from_secs(30);
+const MAX_BACKOFF: Duration = Duration::from_secs(60 * 60);
+
+/// Time after which finished jobs are deleted, dead jobs are kept longer for inspection.
+const DONE_RETENTION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+const DEAD_RETENTION: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Persistent queue of jobs which survive restarts. Jobs are executed by the workers of the
+/// queue service and retried with exponential backoff until `max_attempts` is reached.
+pub struct JobQueue {
+    db: DatabaseRootAccess,
+    handlers: BTreeMap<String, Arc<dyn JobHandler>>,
+    max_attempts: u32,
+    /// Running jobs whose worker did not finish within this duration are claimed again,
+    /// e.g. because the application crashed.
+    visibility_timeout: Duration,
+    notify: Notify,
+    claim_guard: Mutex<()>,
+}
+
+impl JobQueue {
+    pub fn new(
+        db: DatabaseRootAccess,
+        handlers: BTreeMap<String, Arc<dyn JobHandler>>,
+        max_attempts: u32,
+        visibility_timeout: Duration,
+    ) -> Self {
+        Self {
+            db,
+            handlers,
+            max_attempts,
+            visibility_timeout,
+            notify: Notify::new(),
+            claim_guard: Mutex::new(()),
+        }
+    }
+
+    /// Persists a job of the given kind, which is picked up by the next idle worker.
+    pub async fn enqueue(&self, kind: &str, payload: impl Serialize) -> anyhow::Result<()> {
+        if !self.handlers.contains_key(kind) {
+            bail!("no handler for job kind `{}` registered", kind);
+        }
+        let payload = serde_json::to_value(payload)?;
+
+        self.db
+            .query("CREATE job SET kind = $kind, payload = $payload, max_attempts = $max_attempts;")
+            .bind("kind", kind)
+            .bind("payload", payload)
+            .bind("max_attempts", self.max_attempts)
+            .await?
+            .checked()?;
+        debug!(kind, "enqueued job");
+
+        self.notify.notify_one();
+        Ok(())
+    }
+
+    /// Time a worker has for a single attempt before the job is claimed again.
+    pub fn visibility_timeout(&self) -> Duration {
+        self.visibility_timeout
+    }
+
+    pub fn handler(&self, kind: &str) -> Option<Arc<dyn JobHandler>> {
+        self.handlers.get(kind).cloned()
+    }
+
+    /// Resolves as soon as a job was enqueued since the last call.
+    pub async fn notified(&self) {
+        self.notify.notified().await
+    }
+
+    /// Claims the job which is due the longest, if any. Jobs whose visibility timeout expired
+    /// after their last attempt are marked dead instead.
+    pub async fn claim(&self) -> anyhow::Result<Option<Job>> {
+        let lock = self.claim_guard.lock().await;
+        let mut response = self.db
+            .query("UPDATE job SET status = 'dead', locked_until = NONE, time_finished = time::now(), last_error = 'visibility timeout exceeded' WHERE status = 'running' AND locked_until <= time::now() AND attempts >= max_attempts;")
+            .query("UPDATE (SELECT id, run_after FROM job WHERE (status = 'pending' AND run_after <= time::now()) OR (status = 'running' AND locked_until <= time::now()) ORDER BY run_after LIMIT 1).id SET status = 'running', attempts += 1, locked_until = time::now() + $timeout RETURN AFTER;")
+            .bind("timeout", sql::Duration::from(self.visibility_timeout))
+            .await?
+            .checked()?;
+        drop(lock);
+
+        let dead_jobs: Vec<Job> = response.take(0)?;
+        for job in dead_jobs {
+            error!(
+                job = %job.id,
+                kind = job.kind,
+                attempts = job.attempts,
+                "JOB FAILED PERMANENTLY, visibility timeout exceeded"
+            );
+        }
+        let mut claimed_jobs: Vec<Job> = response.take(0)?;
+        Ok(claimed_jobs.pop())
+    }
+
+    /// Marks the job as done, or schedules its retry if it failed. Jobs which failed their last
+    /// attempt are kept in the dead state for inspection.
+    ///
+    /// The result is discarded if the job was claimed again since this attempt started, so the
+    /// state recorded by the newer attempt is not overwritten.
+    pub async fn complete(&self, job: &Job, result: anyhow::Result<()>) -> anyhow::Result<()> {
+        match result {
+            Ok(()) => {
+                let updated: Vec<sql::Thing> = self.db
+                    .query("UPDATE $job SET status = 'done', locked_until = NONE, time_finished = time::now() WHERE status = 'running' AND attempts = $attempts RETURN VALUE id;")
+                    .bind("job", &job.id)
+                    .bind("attempts", job.attempts)
+                    .await?
+                    .checked()?
+                    .take(0)?;
+                if updated.is_empty() {
+                    discard_result(job);
+                    return Ok(());
+                }
+                info!(job = %job.id, kind = job.kind, "job done");
+            }
+            Err(err) if job.attempts >= job.max_attempts => {
+                let updated: Vec<sql::Thing> = self.db
+                    .query("UPDATE $job SET status = 'dead', locked_until = NONE, time_finished = time::now(), last_error = $error WHERE status = 'running' AND attempts = $attempts RETURN VALUE id;")
+                    .bind("job", &job.id)
+                    .bind("attempts", job.attempts)
+                    .bind("error", format!("{:?}", err))
+                    .await?
+                    .checked()?
+                    .take(0)?;
+                if updated.is_empty() {
+                    discard_result(job);
+                    return Ok(());
+                }
+                error!(
+                    job = %job.id,
+                    kind = job.kind,
+                    attempts = job.attempts,
+                    ?err,
+                    "JOB FAILED PERMANENTLY"
+                );
+            }
+            Err(err) => {
+                let backoff = backoff(job.attempts);
+                let run_after = Utc::now() + chrono::Duration::from_std(backoff)?;
+                let updated: Vec<sql::Thing> = self.db
+                    .query("UPDATE $job SET status = 'pending', locked_until = NONE, run_after = $run_after, last_error = $error WHERE status = 'running' AND attempts = $attempts RETURN VALUE id;")
+                    .bind("job", &job.id)
+                    .bind("attempts", job.attempts)
+                    .bind("run_after", sql::Datetime::from(run_after))
+                    .bind("error", format!("{:?}", err))
+                    .await?
+                    .checked()?
+                    .take(0)?;
+                if updated.is_empty() {
+                    discard_result(job);
+                    return Ok(());
+                }
+                warn!(
+                    job = %job.id,
+                    kind = job.kind,
+                    attempts = job.attempts,
+                    ?err,
+                    "job failed, retrying in {}s",
+                    backoff.as_secs()
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Deletes `done` and `dead` jobs which finished longer ago than their retention.
+    /// Returns the amount of deleted jobs.
+    pub async fn prune(&self) -> anyhow::Result<usize> {
+        let deleted: Vec<Job> = self.db
+            .query("DELETE job WHERE (status = 'done' AND time_finished < time::now() - $done_retention) OR (status = 'dead' AND time_finished < time::now() - $dead_retention) RETURN BEFORE;")
+            .bind("done_retention", sql::Duration::from(DONE_RETENTION))
+            .bind("dead_retention", sql::Duration::from(DEAD_RETENTION))
+            .await?
+            .take(0)?;
+        Ok(deleted.len())
+    }
+
+    /// The most recently created jobs, optionally only those with the given status, newest first.
+    pub async fn jobs(&self, status: Option<JobStatus>, limit: u32) -> anyhow::Result<Vec<Job>> {
+        self.db
+            .query("SELECT * FROM job WHERE $status = NONE OR status = $status ORDER BY time_created DESC LIMIT $limit;")
+            .bind("status", status.map(|status| status.as_str()))
+            .bind("limit", limit)
+            .await?
+            .take(0)
+    }
+}
+
+fn discard_result(job: &Job) {
+    warn!(
+        job = %job.id,
+        kind = job.kind,
+        attempts = job.attempts,
+        "job was claimed again before its attempt finished, result discarded"
+    );
+}
+
+fn backoff(attempts: u32) -> Duration {
+    let exponent = attempts.saturating_sub(1).min(16);
+    BASE_BACKOFF
+        .saturating_mul(2u32.pow(exponent))
+        .min(MAX_BACKOFF)
+}
@@ -0,0 +1,77 @@
+use std::time::Duration;
+
+use anyhow::anyhow;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, info_span, Instrument};
+
+use crate::context::MycologContext;
+
+/// Upper bound for waiting on new jobs, so retries and expired visibility timeouts are noticed.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Delay after a failed database access, so a temporarily unavailable database is not hammered.
+const ERROR_BACKOFF: Duration = Duration::from_secs(30);
+
+pub async fn queue_service(
+    context: &MycologContext,
+    worker: u32,
+    shutdown_token: CancellationToken,
+) -> anyhow::Result<()> {
+    let queue = &context.queue;
+
+    info!(worker, "started job queue worker");
+    while !shutdown_token.is_cancelled() {
+        let job = match queue.claim().await {
+            Ok(Some(job)) => job,
+            Ok(None) => {
+                tokio::select! {
+                    _ = shutdown_token.cancelled() => {}
+                    _ = queue.notified() => {}
+                    _ = tokio::time::sleep(POLL_INTERVAL) => {}
+                }
+                continue;
+            }
+            Err(err) => {
+                error!(
+                    worker,
+                    ?err,
+                    "unable to claim job, retrying in {}s",
+                    ERROR_BACKOFF.as_secs()
+                );
+                tokio::select! {
+                    _ = shutdown_token.cancelled() => {}
+                    _ = tokio::time::sleep(ERROR_BACKOFF) => {}
+                }
+                continue;
+            }
+        };
+
+        let span = info_span!("job", id = %job.id, kind = job.kind, attempt = job.attempts);
+        let result = match queue.handler(&job.kind) {
+            // Past its visibility timeout the job may be claimed again, so the attempt is aborted
+            Some(handler) => tokio::time::timeout(
+                queue.visibility_timeout(),
+                handler
+                    .handle(context, job.payload.clone())
+                    .instrument(span),
+            )
+            .await
+            .unwrap_or_else(|_| {
+                Err(anyhow!(
+                    "job exceeded its visibility timeout of {}s",
+                    queue.visibility_timeout().as_secs()
+                ))
+            }),
+            None => Err(anyhow!("no handler for job kind `{}` registered", job.kind)),
+        };
+        // The job stays running and is claimed again once its visibility timeout expired
+        if let Err(err) = queue.complete(&job, result).await {
+            error!(worker, job = %job.id, ?err, "unable to record job result");
+            tokio::select! {
+                _ = shutdown_token.cancelled() => {}
+                _ = tokio::time::sleep(ERROR_BACKOFF) => {}
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use async_trait::async_trait;
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+
+use crate::application::schedules::job::{JobRegistry, ScheduledJob};
+use crate::context::MycologContext;
+
+/// Registers the jobs shipped with the application.
+pub fn create_job_registry() -> anyhow::Result<JobRegistry> {
+    let mut registry = JobRegistry::default();
+    registry.register(CleanImagesJob, "30 3 * * *", true)?;
+    registry.register(ConstrainImagesJob, "15 * * * *", false)?;
+    registry.register(PruneJobsJob, "45 3 * * *", true)?;
+    Ok(registry)
+}
+
+/// Removes image files without database entry and database entries without image file.
+struct CleanImagesJob;
+
+#[async_trait]
+impl ScheduledJob for CleanImagesJob {
+    fn name(&self) -> &str {
+        "clean_images"
+    }
+
+    async fn run(
+        &self,
+        context: &MycologContext,
+        _cancel_token: &CancellationToken,
+    ) -> anyhow::Result<()> {
+        context.images.clean().await
+    }
+}
+
+/// Deletes the oldest images of users exceeding `images.max_bytes_per_user`.
+struct ConstrainImagesJob;
+
+#[async_trait]
+impl ScheduledJob for ConstrainImagesJob {
+    fn name(&self) -> &str {
+        "constrain_images"
+    }
+
+    async fn run(
+        &self,
+        context: &MycologContext,
+        _cancel_token: &CancellationToken,
+    ) -> anyhow::Result<()> {
+        let deleted = context.images.constrain_images().await?;
+        if deleted > 0 {
+            info!(deleted, "deleted images exceeding user storage limits");
+        }
+        Ok(())
+    }
+}
+
+/// Deletes finished jobs of the job queue after their retention.
+struct PruneJobsJob;
+
+#[async_trait]
+impl ScheduledJob for PruneJobsJob {
+    fn name(&self) -> &str {
+        "prune_jobs"
+    }
+
+    async fn run(
+        &self,
+        context: &MycologContext,
+        _cancel_token: &CancellationToken,
+    ) -> anyhow::Result<()> {
+        let deleted = context.queue.prune().await?;
+        if deleted > 0 {
+            info!(deleted, "deleted finished jobs after their retention");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+use std::future::IntoFuture;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail};
+use chrono::{DateTime, Utc};
+use surrealdb_core::sql::Statements;
+use tracing::{error, info, info_span, Instrument};
+
+use crate::application::database::system::Response;
+use crate::application::database::DatabaseRootAccess;
+use crate::application::schedules::history::{ScheduleRun, StatementOutcome};
+use crate::application::schedules::job::ScheduledJob;
+use crate::application::schedules::schedule::ScheduleAction;
+use crate::application::schedules::Schedule;
+use crate::context::MycologContext;
+
+/// Executes the schedule and summarizes the outcome as [ScheduleRun], which is not yet recorded.
+/// Queries are executed on `db`, which is not necessarily the database of `context`.
+pub async fn execute_schedule(
+    context: &MycologContext,
+    db: &DatabaseRootAccess,
+    schedule: &Schedule,
+    time_scheduled: DateTime<Utc>,
+) -> (ScheduleRun, Vec<Response>) {
+    let schedule_name = schedule.name.as_str();
+    let time_started = Utc::now();
+    let (duration, responses, statements) = match &schedule.action {
+        ScheduleAction::Query(statements) => {
+            info!(schedule = schedule_name, "executing database query...");
+            execute_query(db, statements)
+                .instrument(info_span!("schedule_query", schedule = schedule_name))
+                .await
+        }
+        ScheduleAction::Job(job) => {
+            info!(schedule = schedule_name, "executing job...");
+            execute_job(context, job.as_ref())
+                .instrument(info_span!("schedule_job", schedule = schedule_name))
+                .await
+        }
+    };
+    let run = ScheduleRun {
+        schedule: schedule_name.to_string(),
+        time_scheduled,
+        time_started,
+        duration_ms: duration.as_millis() as u64,
+        success: statements.iter().all(|statement| statement.error.is_none()),
+        statements,
+    };
+
+    match run.error() {
+        None => info!(
+            schedule = schedule_name,
+            duration_ms = run.duration_ms,
+            "successfully executed schedule"
+        ),
+        Some(err) => error!(schedule = schedule_name, err, "scheduled execution failed"),
+    }
+    (run, responses)
+}
+
+async fn execute_query(
+    db: &DatabaseRootAccess,
+    statements: &Statements,
+) -> (Duration, Vec<Response>, Vec<StatementOutcome>) {
+    match db.query(statements.clone()).into_future().await {
+        Ok(responses) => {
+            let duration = responses
+                .stats()
+                .iter()
+                .map(|stats| stats.execution_time)
+                .sum::<Duration>();
+            let responses = responses.collect();
+            let statements = responses
+                .iter()
+                .map(StatementOutcome::from)
+                .collect::<Vec<_>>();
+            (duration, responses, statements)
+        }
+        Err(err) => {
+            let statements = vec![StatementOutcome {
+                error: Some(format!("{err:?}")),
+                records: None,
+            }];
+            (Duration::ZERO, Vec::new(), statements)
+        }
+    }
+}
+
+/// Jobs are recorded as a single statement.
+async fn execute_job(
+    context: &MycologContext,
+    job: &dyn ScheduledJob,
+) -> (Duration, Vec<Response>, Vec<StatementOutcome>) {
+    let start = Instant::now();
+    let result = job.run(context, &context.task_cancel_token).await;
+    let statement = StatementOutcome {
+        error: result.err().map(|err| format!("{err:?}")),
+        records: None,
+    };
+    (start.elapsed(), Vec::new(), vec![statement])
+}
+
+/// Runs the schedule immediately, independent of its timing, and returns the responses of its
+/// statements. Regular runs are recorded in the run history.
+///
+/// A dry run executes the statements on a throwaway in-memory copy of the database instead.
+/// Simply cancelling a transaction is not sufficient, because SurrealDB replaces the responses
+/// of cancelled transactions with errors. Jobs can not be dry run.
+pub async fn run_schedule_now(
+    context: &MycologContext,
+    schedule: &Schedule,
+    dry_run: bool,
+) -> anyhow::Result<Vec<Response>> {
+    let db = context.db.auth_root();
+    if dry_run {
+        if let ScheduleAction::Job(_) = schedule.action {
+            bail!(
+                "schedule `{}` is a job and can not be dry run",
+                schedule.name
+            );
+        }
+        let copy = db.in_memory_copy().await?;
+        let (run, responses) = execute_schedule(context, &copy, schedule, Utc::now()).await;
+        return ensure_executed(run, responses);
+    }
+
+    let (run, responses) = execute_schedule(context, &db, schedule, Utc::now()).await;
+    run.record(&db).await?;
+    ensure_executed(run, responses)
+}
+
+/// Turns a run without any responses into an error, as the query did not execute at all.
+fn ensure_executed(run: ScheduleRun, responses: Vec<Response>) -> anyhow::Result<Vec<Response>> {
+    if responses.is_empty()
+        && let Some(err) = run.error()
+    {
+        return Err(anyhow!(
+            "schedule `{}` could not be executed: {err}",
+            run.schedule
+        ));
+    }
+    Ok(responses)
+}
@@ -0,0 +1,100 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb_core::sql::{Datetime, Value};
+
+use crate::application::database::system::Response;
+use crate::application::database::DatabaseRootAccess;
+
+/// A single execution of a schedule as recorded in the `schedule_run` table.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ScheduleRun {
+    pub schedule: String,
+    pub time_scheduled: DateTime<Utc>,
+    pub time_started: DateTime<Utc>,
+    /// Summed up execution time of all statements.
+    pub duration_ms: u64,
+    pub success: bool,
+    pub statements: Vec<StatementOutcome>,
+}
+
+/// Outcome of a single statement of a schedule run.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StatementOutcome {
+    pub error: Option<String>,
+    /// Amount of records returned by the statement, which is the amount of affected records for
+    /// `CREATE`, `UPDATE`, `DELETE` and `RELATE` statements.
+    pub records: Option<u64>,
+}
+
+impl From<&Response> for StatementOutcome {
+    fn from(response: &Response) -> Self {
+        match &response.result {
+            Ok(Value::Array(array)) => StatementOutcome {
+                error: None,
+                records: Some(array.len() as u64),
+            },
+            Ok(Value::Object(_)) => StatementOutcome {
+                error: None,
+                records: Some(1),
+            },
+            Ok(_) => StatementOutcome {
+                error: None,
+                records: None,
+            },
+            Err(err) => StatementOutcome {
+                error: Some(err.to_string()),
+                records: None,
+            },
+        }
+    }
+}
+
+impl ScheduleRun {
+    pub async fn record(&self, db: &DatabaseRootAccess) -> anyhow::Result<()> {
+        db.query("CREATE schedule_run SET schedule = $schedule, time_scheduled = $time_scheduled, time_started = $time_started, duration_ms = $duration_ms, success = $success, statements = $statements;")
+            .bind("schedule", &self.schedule)
+            .bind("time_scheduled", Datetime::from(self.time_scheduled))
+            .bind("time_started", Datetime::from(self.time_started))
+            .bind("duration_ms", self.duration_ms)
+            .bind("success", self.success)
+            .bind("statements", &self.statements)
+            .await?
+            .checked()?;
+        Ok(())
+    }
+
+    /// The first error of the run, if any.
+    pub fn error(&self) -> Option<&str> {
+        self.statements
+            .iter()
+            .find_map(|statement| statement.error.as_deref())
+    }
+}
+
+/// The most recent schedule runs, optionally only of the given schedule, newest first.
+pub async fn recent_schedule_runs(
+    db: &DatabaseRootAccess,
+    schedule: Option<&str>,
+    limit: u32,
+) -> anyhow::Result<Vec<ScheduleRun>> {
+    db.query("SELECT * FROM schedule_run WHERE $schedule = NONE OR schedule = $schedule ORDER BY time_started DESC LIMIT $limit;")
+        .bind("schedule", schedule)
+        .bind("limit", limit)
+        .await?
+        .take(0)
+}
+
+/// Amount of consecutive failed runs of the schedule, counting at most `limit` runs.
+pub async fn consecutive_schedule_failures(
+    db: &DatabaseRootAccess,
+    schedule: &str,
+    limit: u32,
+) -> anyhow::Result<u32> {
+    let successes: Vec<bool> = db
+        .query("SELECT VALUE success FROM schedule_run WHERE schedule = $schedule ORDER BY time_started DESC LIMIT $limit;")
+        .bind("schedule", schedule)
+        .bind("limit", limit)
+        .await?
+        .take(0)?;
+    Ok(successes.iter().take_while(|success| !**success).count() as u32)
+}
@@ -0,0 +1,63 @@
+use std::sync::Arc;
+
+use anyhow::bail;
+use async_trait::async_trait;
+use tokio_util::sync::CancellationToken;
+
+use crate::application::schedules::schedule::{Schedule, ScheduleAction, ScheduleHeader};
+use crate::context::MycologContext;
+
+/// Background work implemented in Rust, which is executed and recorded like schedule files.
+#[async_trait]
+pub trait ScheduledJob: Send + Sync {
+    /// Unique name of the job, shared with the names of schedule files.
+    fn name(&self) -> &str;
+
+    /// Executes the job. Long-running jobs should stop early once `cancel_token` is cancelled.
+    async fn run(
+        &self,
+        context: &MycologContext,
+        cancel_token: &CancellationToken,
+    ) -> anyhow::Result<()>;
+}
+
+/// All jobs known to the schedule service.
+#[derive(Clone, Default)]
+pub struct JobRegistry {
+    schedules: Vec<Schedule>,
+}
+
+impl JobRegistry {
+    /// Registers the job to run according to the cron expression, which is evaluated in UTC.
+    pub fn register(
+        &mut self,
+        job: impl ScheduledJob + 'static,
+        cron: &str,
+        catch_up: bool,
+    ) -> anyhow::Result<()> {
+        let name = job.name().to_string();
+        if self.get(&name).is_some() {
+            bail!("job `{name}` is registered more than once");
+        }
+
+        let header = ScheduleHeader {
+            catch_up,
+            ..Default::default()
+        };
+        self.schedules.push(Schedule::new(
+            name,
+            header,
+            cron,
+            ScheduleAction::Job(Arc::new(job)),
+        )?);
+        Ok(())
+    }
+
+    pub fn schedules(&self) -> &[Schedule] {
+        &self.schedules
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Schedule> {
+        self.schedules.iter().find(|schedule| schedule.name == name)
+    }
+}
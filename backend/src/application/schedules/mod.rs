@@ -1,60 +1,117 @@
 use std::path::PathBuf;
 use std::sync::Arc;
 
-use surrealdb_core::sql::Statements;
 use tracing::{error, info, warn};
 
 use crate::application::database::load_surql_file;
+use crate::application::schedules::schedule::ScheduleHeader;
 use crate::application::schedules::service::schedule_service;
 use crate::context::MycologContext;
 
+pub use builtin::create_job_registry;
+pub use execution::run_schedule_now;
+pub use history::{recent_schedule_runs, ScheduleRun};
+pub use job::{JobRegistry, ScheduledJob};
+pub use schedule::{Schedule, ScheduleAction};
+
+mod builtin;
+mod execution;
+mod history;
+mod job;
+mod schedule;
 mod service;
 
 #[derive(Clone)]
 pub struct ScheduleQueries {
-    hourly: Option<Statements>,
-    daily: Option<Statements>,
-    weekly: Option<Statements>,
+    schedules: Vec<Schedule>,
+}
+
+impl ScheduleQueries {
+    pub fn schedules(&self) -> &[Schedule] {
+        &self.schedules
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Schedule> {
+        self.schedules.iter().find(|schedule| schedule.name == name)
+    }
+}
+
+/// Cron expressions of the schedule files which existed before schedules declared their timing,
+/// used if these files have no `@cron` header.
+fn legacy_cron(name: &str) -> Option<&'static str> {
+    match name {
+        "hourly" => Some("0 * * * *"),
+        "daily" => Some("0 0 * * *"),
+        "weekly" => Some("0 0 * * 1"),
+        _ => None,
+    }
 }
 
+/// Loads every `*.surql` file in the folder as a schedule named after the file stem.
 pub async fn load_schedule_queries(folder: impl Into<PathBuf>) -> anyhow::Result<ScheduleQueries> {
     let folder = folder.into();
-    let hourly_file = folder.join("hourly.surql");
-    let daily_file = folder.join("daily.surql");
-    let weekly_file = folder.join("weekly.surql");
-
-    async fn get_statements_from_file(
-        path: impl Into<PathBuf>,
-    ) -> anyhow::Result<Option<Statements>> {
-        let path = path.into();
-        if path.is_file() {
-            let statements = load_surql_file(path.clone()).await?.statements;
-            if !statements.0.is_empty() {
-                Ok(Some(statements))
-            } else {
-                warn!(
-                    "scheduling file {} has been found but was empty and thus ignored",
-                    path.display()
-                );
-                Ok(None)
-            }
-        } else {
-            Ok(None)
+
+    let mut paths = Vec::new();
+    let mut entries = tokio::fs::read_dir(&folder).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.is_file()
+            && path
+                .extension()
+                .is_some_and(|extension| extension == "surql")
+        {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    let mut schedules = Vec::new();
+    for path in paths {
+        let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            warn!(
+                "scheduling file {} has no valid name and was ignored",
+                path.display()
+            );
+            continue;
+        };
+        let name = name.to_string();
+
+        let file = load_surql_file(&path).await?;
+        if file.statements.0.is_empty() {
+            warn!(
+                "scheduling file {} has been found but was empty and thus ignored",
+                path.display()
+            );
+            continue;
         }
+
+        let mut header = ScheduleHeader::parse(&file.content)
+            .map_err(|err| err.context(format!("invalid header in {}", path.display())))?;
+        let Some(cron) = header.cron.take().or(legacy_cron(&name).map(String::from)) else {
+            warn!(
+                "scheduling file {} does not declare a `@cron` schedule and was ignored",
+                path.display()
+            );
+            continue;
+        };
+
+        schedules.push(Schedule::new(
+            name,
+            header,
+            &cron,
+            ScheduleAction::Query(file.statements),
+        )?);
     }
 
-    Ok(ScheduleQueries {
-        hourly: get_statements_from_file(hourly_file).await?,
-        daily: get_statements_from_file(daily_file).await?,
-        weekly: get_statements_from_file(weekly_file).await?,
-    })
+    Ok(ScheduleQueries { schedules })
 }
 
 pub async fn schedule_task(context: Arc<MycologContext>) {
-    let db = context.db.auth_root();
     let shutdown_token = context.task_cancel_token.clone();
 
-    if let Err(err) = schedule_service(db, &context.schedules, shutdown_token).await {
+    if let Err(err) =
+        schedule_service(&context, context.schedules.subscribe(), shutdown_token).await
+    {
         error!(
             ?err,
             "database schedule service stopped working due to error"
@@ -0,0 +1,160 @@
+use std::fmt::{Debug, Formatter};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use croner::Cron;
+use surrealdb_core::sql::Statements;
+
+use crate::application::schedules::job::ScheduledJob;
+
+/// A schedule file or job together with its timing. Schedule files declare their timing in a
+/// header comment:
+///
+/// ```surql
+/// -- @cron 0 3 * * *
+/// -- @timezone Europe/Vienna
+/// -- @catch-up
+/// ```
+///
+/// The timezone defaults to UTC, missed runs are only caught up if `@catch-up` is present.
+#[derive(Clone, Debug)]
+pub struct Schedule {
+    pub name: String,
+    pub cron: Cron,
+    pub timezone: Tz,
+    pub catch_up: bool,
+    pub action: ScheduleAction,
+}
+
+/// What is executed whenever a schedule is due.
+#[derive(Clone)]
+pub enum ScheduleAction {
+    Query(Statements),
+    Job(Arc<dyn ScheduledJob>),
+}
+
+/// Timing declared in the header comment of a schedule file.
+#[derive(Default)]
+pub struct ScheduleHeader {
+    pub cron: Option<String>,
+    pub timezone: Option<String>,
+    pub catch_up: bool,
+}
+
+impl Schedule {
+    pub fn new(
+        name: String,
+        header: ScheduleHeader,
+        cron: &str,
+        action: ScheduleAction,
+    ) -> anyhow::Result<Self> {
+        let cron = Cron::new(cron)
+            .with_seconds_optional()
+            .parse()
+            .map_err(|err| anyhow!("schedule `{name}` has invalid cron expression: {err}"))?;
+        let timezone = match &header.timezone {
+            Some(timezone) => Tz::from_str(timezone)
+                .map_err(|err| anyhow!("schedule `{name}` has invalid timezone: {err}"))?,
+            None => Tz::UTC,
+        };
+
+        Ok(Schedule {
+            name,
+            cron,
+            timezone,
+            catch_up: header.catch_up,
+            action,
+        })
+    }
+
+    /// The first time this schedule is due strictly after the given time.
+    pub fn next_run_after(&self, time: DateTime<Utc>) -> anyhow::Result<DateTime<Utc>> {
+        let next_run = self
+            .cron
+            .find_next_occurrence(&time.with_timezone(&self.timezone), false)
+            .map_err(|err| anyhow!("schedule `{}` has no next run: {err}", self.name))?;
+        Ok(next_run.with_timezone(&Utc))
+    }
+}
+
+impl ScheduleHeader {
+    /// Parses the `-- @key value` lines of the leading comment block.
+    pub fn parse(content: &str) -> anyhow::Result<Self> {
+        let mut header = ScheduleHeader::default();
+        for line in content.lines().map(str::trim) {
+            if line.is_empty() {
+                continue;
+            }
+            let Some(comment) = line.strip_prefix("--") else {
+                break;
+            };
+            let Some(annotation) = comment.trim().strip_prefix('@') else {
+                continue;
+            };
+
+            let (key, value) = annotation
+                .split_once(char::is_whitespace)
+                .map(|(key, value)| (key, value.trim()))
+                .unwrap_or((annotation, ""));
+            match key {
+                "cron" => header.cron = Some(value.to_string()),
+                "timezone" => header.timezone = Some(value.to_string()),
+                "catch-up" => header.catch_up = value.is_empty() || value == "true",
+                _ => return Err(anyhow!("unknown schedule annotation `@{key}`")),
+            }
+        }
+
+        Ok(header)
+    }
+}
+
+impl Debug for ScheduleAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScheduleAction::Query(statements) => f.debug_tuple("Query").field(statements).finish(),
+            ScheduleAction::Job(job) => f.debug_tuple("Job").field(&job.name()).finish(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_header() {
+        let header = ScheduleHeader::parse(
+            "-- @cron 0 3 * * *\n-- @timezone Europe/Vienna\n-- @catch-up\nDELETE session;",
+        )
+        .unwrap();
+        assert_eq!(header.cron.as_deref(), Some("0 3 * * *"));
+        assert_eq!(header.timezone.as_deref(), Some("Europe/Vienna"));
+        assert!(header.catch_up);
+    }
+
+    #[test]
+    fn ignores_plain_comments_and_blank_lines() {
+        let header = ScheduleHeader::parse(
+            "\n-- Deletes expired sessions\n\n--   @cron   0 * * * *  \n--@catch-up false\n",
+        )
+        .unwrap();
+        assert_eq!(header.cron.as_deref(), Some("0 * * * *"));
+        assert_eq!(header.timezone, None);
+        assert!(!header.catch_up);
+    }
+
+    #[test]
+    fn stops_at_first_statement() {
+        let header = ScheduleHeader::parse("DELETE session;\n-- @cron 0 * * * *").unwrap();
+        assert_eq!(header.cron, None);
+        assert!(!header.catch_up);
+    }
+
+    #[test]
+    fn rejects_unknown_annotations() {
+        assert!(ScheduleHeader::parse("-- @crontab 0 * * * *").is_err());
+    }
+}
@@ -1,51 +1,224 @@
-use std::future::IntoFuture;
 use std::time::Duration;
 
-use tokio::time::{interval, Interval};
+use chrono::{DateTime, Utc};
+use tokio::sync::watch;
 use tokio_util::sync::CancellationToken;
-use tracing::{debug, error, info, info_span, Instrument};
+use tracing::{debug, error, info, warn};
 
 use crate::application::database::DatabaseRootAccess;
-use crate::application::ScheduleQueries;
+use crate::application::email::Recipient;
+use crate::application::schedules::execution::execute_schedule;
+use crate::application::schedules::history::{consecutive_schedule_failures, ScheduleRun};
+use crate::application::schedules::job::JobRegistry;
+use crate::application::schedules::Schedule;
+use crate::application::{EmailManager, ScheduleQueries};
 use crate::config::MycologConfig;
+use crate::context::MycologContext;
+
+/// Upper bound for sleeping between checks, so jumps of the system clock are noticed in time.
+const MAX_SLEEP: Duration = Duration::from_secs(60);
+
+struct PlannedRun {
+    schedule: Schedule,
+    next_run: DateTime<Utc>,
+    consecutive_failures: u32,
+}
 
 pub async fn schedule_service(
-    db: DatabaseRootAccess,
-    queries: &ScheduleQueries,
+    context: &MycologContext,
+    mut queries: watch::Receiver<ScheduleQueries>,
     shutdown_token: CancellationToken,
 ) -> anyhow::Result<()> {
-    let mut hourly_timer = interval(Duration::from_hours(1));
-    let mut daily_timer = interval(Duration::from_days(1));
-    let mut weekly_timer = interval(Duration::from_weeks(1));
+    let db = context.db.auth_root();
+    let alert_after_failures = context.config.schedule_alert_after_failures;
 
     info!("started schedule service");
     while !shutdown_token.is_cancelled() {
-        let (schedule, Some(statements)) = tokio::select!(
-            _ = shutdown_token.cancelled() => break,
-            _ = hourly_timer.tick() => ("hourly", queries.hourly.as_ref()),
-            _ = daily_timer.tick() => ("daily", queries.daily.as_ref()),
-            _ = weekly_timer.tick() => ("weekly", queries.weekly.as_ref())
-        ) else {
-            continue;
-        };
-        info!(schedule, "executing database query...");
-        let responses = match db
-            .query(statements.clone())
-            .into_future()
-            .instrument(info_span!("schedule_query", schedule))
-            .await
+        let current_queries = queries.borrow_and_update().clone();
+        let mut planned_runs =
+            plan_runs(&db, &current_queries, &context.jobs, alert_after_failures).await;
+
+        loop {
+            let now = Utc::now();
+            let mut finished_schedules = Vec::new();
+            for planned_run in planned_runs.iter_mut() {
+                if planned_run.next_run > now {
+                    continue;
+                }
+                let schedule = &planned_run.schedule;
+                let (run, _) = execute_schedule(context, &db, schedule, planned_run.next_run).await;
+                if let Err(err) = run.record(&db).await {
+                    warn!(
+                        schedule = schedule.name,
+                        ?err,
+                        "unable to record schedule run"
+                    );
+                }
+
+                if run.success {
+                    planned_run.consecutive_failures = 0;
+                } else {
+                    planned_run.consecutive_failures += 1;
+                    // Only alert once per series of failures
+                    if planned_run.consecutive_failures == alert_after_failures {
+                        alert_failures(
+                            &context.config,
+                            &context.email,
+                            &run,
+                            planned_run.consecutive_failures,
+                        )
+                        .await;
+                    }
+                }
+
+                // Multiple missed runs are caught up by a single execution
+                match schedule.next_run_after(now) {
+                    Ok(next_run) => {
+                        planned_run.next_run = next_run;
+                        debug!(
+                            schedule = schedule.name,
+                            %next_run,
+                            "planned next schedule run"
+                        );
+                    }
+                    Err(err) => {
+                        error!(
+                            schedule = schedule.name,
+                            ?err,
+                            "unable to plan next schedule run, schedule is not run anymore"
+                        );
+                        finished_schedules.push(schedule.name.clone());
+                    }
+                }
+            }
+            planned_runs
+                .retain(|planned_run| !finished_schedules.contains(&planned_run.schedule.name));
+
+            let sleep_duration = planned_runs
+                .iter()
+                .map(|planned_run| planned_run.next_run)
+                .min()
+                .and_then(|next_run| (next_run - Utc::now()).to_std().ok())
+                .map_or(MAX_SLEEP, |duration| duration.min(MAX_SLEEP));
+            tokio::select!(
+                _ = shutdown_token.cancelled() => return Ok(()),
+                _ = tokio::time::sleep(sleep_duration) => {},
+                Ok(()) = queries.changed() => {
+                    info!("schedules changed, planning runs again");
+                    break;
+                }
+            );
+        }
+    }
+    Ok(())
+}
+
+async fn plan_runs(
+    db: &DatabaseRootAccess,
+    queries: &ScheduleQueries,
+    jobs: &JobRegistry,
+    alert_after_failures: u32,
+) -> Vec<PlannedRun> {
+    let mut planned_runs = Vec::new();
+    for schedule in jobs.schedules().iter().chain(queries.schedules()) {
+        if planned_runs
+            .iter()
+            .any(|planned_run: &PlannedRun| planned_run.schedule.name == schedule.name)
         {
-            Ok(responses) => responses,
+            warn!(
+                schedule = schedule.name,
+                "schedule file has the same name as a job and is ignored"
+            );
+            continue;
+        }
+
+        let next_run = match first_run(db, schedule).await {
+            Ok(next_run) => next_run,
             Err(err) => {
-                error!(schedule, ?err, "query for database failed");
+                error!(
+                    schedule = schedule.name,
+                    ?err,
+                    "unable to plan first schedule run, schedule is not run"
+                );
                 continue;
             }
         };
-        if let Err(err) = responses.checked() {
-            error!(schedule, ?err, "query for database responded with error");
-            continue;
-        }
-        info!(schedule, "successfully executed database query");
+        // Failures are only counted for alerting, so scheduling continues without them
+        let consecutive_failures =
+            match consecutive_schedule_failures(db, &schedule.name, alert_after_failures).await {
+                Ok(consecutive_failures) => consecutive_failures,
+                Err(err) => {
+                    error!(
+                        schedule = schedule.name,
+                        ?err,
+                        "unable to count consecutive schedule failures, counting from zero"
+                    );
+                    0
+                }
+            };
+        debug!(schedule = schedule.name, %next_run, "planned first schedule run");
+        planned_runs.push(PlannedRun {
+            schedule: schedule.clone(),
+            next_run,
+            consecutive_failures,
+        });
+    }
+    planned_runs
+}
+
+/// Schedules with catch-up enabled continue after their last recorded run, so runs missed during
+/// downtime are executed immediately. All other schedules wait for their next regular run.
+async fn first_run(db: &DatabaseRootAccess, schedule: &Schedule) -> anyhow::Result<DateTime<Utc>> {
+    let now = Utc::now();
+    if !schedule.catch_up {
+        return schedule.next_run_after(now);
+    }
+
+    let last_run: Option<DateTime<Utc>> = db
+        .query("RETURN (SELECT VALUE time_scheduled FROM schedule_run WHERE schedule = $schedule ORDER BY time_scheduled DESC LIMIT 1)[0];")
+        .bind("schedule", &schedule.name)
+        .await?
+        .take(0)?;
+    match last_run {
+        Some(last_run) => schedule.next_run_after(last_run),
+        None => schedule.next_run_after(now),
+    }
+}
+
+async fn alert_failures(
+    config: &MycologConfig,
+    email: &EmailManager,
+    run: &ScheduleRun,
+    failures: u32,
+) {
+    let Some(admin_address) = &config.email_admin_address else {
+        warn!(
+            schedule = run.schedule,
+            failures, "schedule keeps failing but no `email.admin_address` is configured"
+        );
+        return;
+    };
+
+    let recipient = Recipient::new(admin_address)
+        .bind("schedule", &run.schedule)
+        .bind("failures", failures)
+        .bind("error", run.error().unwrap_or_default());
+    let result = email
+        .sumbit_email(
+            "schedule_failed",
+            &format!("Mycolog schedule `{}` is failing", run.schedule),
+            vec![recipient],
+        )
+        .await;
+    match result {
+        Ok(()) => info!(
+            schedule = run.schedule,
+            failures, "alerted admin about failing schedule"
+        ),
+        Err(err) => error!(
+            schedule = run.schedule,
+            ?err,
+            "unable to alert admin about failing schedule"
+        ),
     }
-    Ok(())
 }
@@ -0,0 +1,17 @@
+use std::sync::Arc;
+
+use tracing::{error, info};
+
+use crate::application::secrets::service::secrets_reload_service;
+use crate::context::MycologContext;
+
+mod service;
+
+pub async fn secrets_reload_task(context: Arc<MycologContext>) {
+    let shutdown_token = context.task_cancel_token.clone();
+
+    if let Err(err) = secrets_reload_service(&context, shutdown_token).await {
+        error!(?err, "secrets reload service stopped working due to error");
+    }
+    info!("stopped secrets reload service");
+}
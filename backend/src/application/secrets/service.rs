@@ -0,0 +1,37 @@
+use tokio::signal::unix::{signal, SignalKind};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info};
+
+use crate::context::MycologContext;
+use crate::secrets::try_parse_secrets;
+
+/// Reloads the secrets on SIGHUP, so the admin token and the webhook signing key can be rotated
+/// without restarting.
+pub async fn secrets_reload_service(
+    context: &MycologContext,
+    shutdown_token: CancellationToken,
+) -> anyhow::Result<()> {
+    let mut hangup = signal(SignalKind::hangup())?;
+
+    info!("started secrets reload service");
+    loop {
+        tokio::select!(
+            _ = shutdown_token.cancelled() => break,
+            received = hangup.recv() => if received.is_none() {
+                break;
+            }
+        );
+
+        match try_parse_secrets(&context.config) {
+            Ok(reloaded) => {
+                context.secrets.rotate(&reloaded);
+                info!("reloaded admin token and webhook signing key");
+            }
+            Err(err) => error!(
+                ?err,
+                "reloaded secrets are invalid, keeping previous version"
+            ),
+        }
+    }
+    Ok(())
+}
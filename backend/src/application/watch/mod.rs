@@ -0,0 +1,21 @@
+use std::sync::Arc;
+
+use tracing::{error, info};
+
+use crate::application::watch::service::watch_service;
+use crate::context::MycologContext;
+
+mod service;
+
+pub async fn watch_task(context: Arc<MycologContext>) {
+    if !context.config.watch_enabled {
+        info!("file watching is disabled");
+        return;
+    }
+    let shutdown_token = context.task_cancel_token.clone();
+
+    if let Err(err) = watch_service(&context, shutdown_token).await {
+        error!(?err, "file watch service stopped working due to error");
+    }
+    info!("stopped file watch service");
+}
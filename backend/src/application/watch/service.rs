@@ -0,0 +1,202 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use tokio::sync::mpsc::unbounded_channel;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+use crate::application::email::load_email_files;
+use crate::application::{load_schedule_queries, MigrationManager};
+use crate::config::MycologConfig;
+use crate::context::MycologContext;
+
+/// Editors usually emit several events per save, which are collected into a single reload.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum WatchedFolder {
+    Schedules,
+    Emails,
+    Migrations,
+}
+
+impl WatchedFolder {
+    const ALL: [WatchedFolder; 3] = [
+        WatchedFolder::Schedules,
+        WatchedFolder::Emails,
+        WatchedFolder::Migrations,
+    ];
+
+    fn path(self, config: &MycologConfig) -> &Path {
+        match self {
+            WatchedFolder::Schedules => &config.schedules_dir,
+            WatchedFolder::Emails => &config.emails_dir,
+            WatchedFolder::Migrations => &config.migrations_dir,
+        }
+    }
+
+    /// The folder containing the changed file. Folders are compared by their canonicalized
+    /// paths, since different folders may share the same name.
+    fn of(file: &Path, watched: &[(WatchedFolder, PathBuf)]) -> Option<Self> {
+        let folder = file.parent()?.canonicalize().ok()?;
+        watched
+            .iter()
+            .find(|(_, path)| *path == folder)
+            .map(|(watched, _)| *watched)
+    }
+}
+
+pub async fn watch_service(
+    context: &MycologContext,
+    shutdown_token: CancellationToken,
+) -> anyhow::Result<()> {
+    let (sender, mut receiver) = unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+        let _ = sender.send(event);
+    })?;
+    let mut watched = Vec::new();
+    for folder in WatchedFolder::ALL {
+        let path = folder.path(&context.config);
+        if !path.is_dir() {
+            warn!(dir = %path.display(), "directory is missing and can not be watched");
+            continue;
+        }
+        watcher.watch(path, RecursiveMode::NonRecursive)?;
+        watched.push((folder, path.canonicalize()?));
+    }
+
+    info!("started file watch service");
+    loop {
+        let Some(event) = tokio::select!(
+            _ = shutdown_token.cancelled() => break,
+            event = receiver.recv() => event
+        ) else {
+            break;
+        };
+
+        let mut changed_folders = BTreeSet::new();
+        collect_changed_folders(&watched, event, &mut changed_folders);
+        let debounce = tokio::time::sleep(DEBOUNCE);
+        tokio::pin!(debounce);
+        loop {
+            tokio::select!(
+                _ = &mut debounce => break,
+                Some(event) = receiver.recv() => collect_changed_folders(&watched, event, &mut changed_folders)
+            );
+        }
+
+        for folder in changed_folders {
+            reload_folder(context, folder).await;
+        }
+    }
+    Ok(())
+}
+
+fn collect_changed_folders(
+    watched: &[(WatchedFolder, PathBuf)],
+    event: notify::Result<Event>,
+    changed_folders: &mut BTreeSet<WatchedFolder>,
+) {
+    let event = match event {
+        Ok(event) => event,
+        Err(err) => {
+            warn!(?err, "file watcher reported error");
+            return;
+        }
+    };
+    if let EventKind::Access(_) = event.kind {
+        return;
+    }
+    debug!(?event.kind, ?event.paths, "watched file changed");
+    changed_folders.extend(
+        event
+            .paths
+            .iter()
+            .filter_map(|path| WatchedFolder::of(path, watched)),
+    );
+}
+
+/// Reloads the content of the folder. The currently loaded version is kept if the new one
+/// fails to load.
+async fn reload_folder(context: &MycologContext, folder: WatchedFolder) {
+    match folder {
+        WatchedFolder::Schedules => match load_schedule_queries(folder.path(&context.config)).await
+        {
+            Ok(queries) => {
+                info!(
+                    schedules = queries.schedules().len(),
+                    "reloaded schedule files"
+                );
+                context.schedules.send_replace(queries);
+            }
+            Err(err) => error!(
+                ?err,
+                "changed schedule files are invalid, keeping previous version"
+            ),
+        },
+        WatchedFolder::Emails => match load_email_files(folder.path(&context.config)).await {
+            Ok(emails) => {
+                info!(emails = emails.len(), "reloaded email templates");
+                context.email.replace_emails(emails).await;
+            }
+            Err(err) => error!(
+                ?err,
+                "changed email templates are invalid, keeping previous version"
+            ),
+        },
+        WatchedFolder::Migrations => {
+            if !context.config.watch_apply_migrations {
+                info!("migration files changed, they are applied on next startup");
+                return;
+            }
+            let result: anyhow::Result<u32> = try {
+                let manager = MigrationManager::new(folder.path(&context.config)).await?;
+                manager
+                    .apply_migrations(
+                        &context.db.auth_root(),
+                        Some(&context.images),
+                        context.config.migration_drift_policy,
+                    )
+                    .await?
+            };
+            match result {
+                Ok(count) => info!(count, "applied new migration files"),
+                Err(err) => error!(?err, "unable to apply changed migration files"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folders_with_same_name_are_distinguished() {
+        let root = std::env::temp_dir().join(format!("mycolog_watch_{}", std::process::id()));
+        let migrations = root.join("a").join("migrations");
+        let schedules = root.join("b").join("migrations");
+        std::fs::create_dir_all(&migrations).unwrap();
+        std::fs::create_dir_all(&schedules).unwrap();
+        let watched = [
+            (
+                WatchedFolder::Migrations,
+                migrations.canonicalize().unwrap(),
+            ),
+            (WatchedFolder::Schedules, schedules.canonicalize().unwrap()),
+        ];
+
+        assert_eq!(
+            WatchedFolder::of(&schedules.join("daily.surql"), &watched),
+            Some(WatchedFolder::Schedules)
+        );
+        assert_eq!(
+            WatchedFolder::of(&migrations.join("0001_init.surql"), &watched),
+            Some(WatchedFolder::Migrations)
+        );
+        assert_eq!(WatchedFolder::of(&root.join("other.surql"), &watched), None);
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+use crate::application::JobStatus;
+use crate::utils::serde::empty_string_as_none;
+
+#[derive(Serialize, Deserialize)]
+pub struct JobsOptions {
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub status: Option<JobStatus>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub limit: Option<u32>,
+}
@@ -0,0 +1,30 @@
+use std::sync::Arc;
+
+use axum::extract::{Query, State};
+use axum::routing::get;
+use axum::{Json, Router};
+
+use crate::application::database::DatabaseRootAccess;
+use crate::application::web::error::ResponseResult;
+use crate::application::web::routes::api::admin::jobs::data::JobsOptions;
+use crate::application::Job;
+use crate::context::MycologContext;
+
+mod data;
+
+pub fn jobs_router(context: &Arc<MycologContext>) -> Router<Arc<MycologContext>> {
+    Router::new().route("/", get(handle_jobs))
+}
+
+/// Lists the most recently enqueued jobs, optionally filtered by status (e.g. `dead`).
+async fn handle_jobs(
+    State(context): State<Arc<MycologContext>>,
+    _db: DatabaseRootAccess,
+    Query(options): Query<JobsOptions>,
+) -> ResponseResult<Json<Vec<Job>>> {
+    let jobs = context
+        .queue
+        .jobs(options.status, options.limit.unwrap_or(50))
+        .await?;
+    Ok(Json(jobs))
+}
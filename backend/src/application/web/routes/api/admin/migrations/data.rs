@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+use crate::utils::serde::empty_string_as_none;
+
+#[derive(Serialize, Deserialize)]
+pub struct RollbackOptions {
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub count: Option<u32>,
+}
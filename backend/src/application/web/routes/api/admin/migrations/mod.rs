@@ -0,0 +1,64 @@
+use std::sync::Arc;
+
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use tracing::{info, instrument, Level};
+
+use crate::application::database::DatabaseRootAccess;
+use crate::application::web::error::{ResponseErrorExt, ResponseResult};
+use crate::application::web::routes::api::admin::migrations::data::RollbackOptions;
+use crate::application::{MigrationManager, MigrationPlan};
+use crate::context::MycologContext;
+
+mod data;
+
+pub fn migrations_router(context: &Arc<MycologContext>) -> Router<Arc<MycologContext>> {
+    Router::new()
+        .route("/", get(handle_plan))
+        .route("/dry_run", post(handle_dry_run))
+        .route("/rollback", post(handle_rollback))
+}
+
+/// Reports the applied, pending and drifted migrations.
+async fn handle_plan(
+    State(context): State<Arc<MycologContext>>,
+    db: DatabaseRootAccess,
+) -> ResponseResult<Json<MigrationPlan>> {
+    let manager = MigrationManager::new(&context.config.migrations_dir).await?;
+    Ok(Json(manager.plan(&db).await?))
+}
+
+/// Applies the pending migrations to an in-memory copy of the database.
+/// Returns the file names of the validated migrations.
+#[instrument(level = Level::DEBUG, skip_all)]
+async fn handle_dry_run(
+    State(context): State<Arc<MycologContext>>,
+    db: DatabaseRootAccess,
+) -> ResponseResult<Json<Vec<String>>> {
+    let manager = MigrationManager::new(&context.config.migrations_dir).await?;
+    let validated = manager
+        .dry_run_migrations(&db)
+        .await
+        .map_err(|err| err.with_code(StatusCode::UNPROCESSABLE_ENTITY))?;
+    info!(?validated, "validated pending migrations");
+    Ok(Json(validated))
+}
+
+/// Reverts the most recently applied migrations, the last one if no count is given.
+/// Returns the file names of the reverted migrations.
+#[instrument(level = Level::DEBUG, skip_all, fields(count = ?options.count))]
+async fn handle_rollback(
+    State(context): State<Arc<MycologContext>>,
+    db: DatabaseRootAccess,
+    Query(options): Query<RollbackOptions>,
+) -> ResponseResult<Json<Vec<String>>> {
+    let manager = MigrationManager::new(&context.config.migrations_dir).await?;
+    let reverted = manager
+        .rollback_migrations(&db, options.count.unwrap_or(1))
+        .await
+        .map_err(|err| err.with_code(StatusCode::UNPROCESSABLE_ENTITY))?;
+    info!(?reverted, "rolled back migrations");
+    Ok(Json(reverted))
+}
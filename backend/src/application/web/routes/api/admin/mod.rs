@@ -0,0 +1,79 @@
+use std::sync::Arc;
+
+use axum::extract::{Request, State};
+use axum::http::HeaderMap;
+use axum::middleware::Next;
+use axum::response::Response;
+use axum::routing::head;
+use axum::{Extension, RequestExt, Router};
+use tracing::{debug, error, instrument, trace, trace_span, Level};
+
+use crate::application::web::routes::api::admin::jobs::jobs_router;
+use crate::application::web::routes::api::admin::migrations::migrations_router;
+use crate::application::web::routes::api::admin::schedules::schedules_router;
+use crate::application::web::routes::api::admin::tokens::{
+    record_admin_token_use, should_record_admin_token_use, tokens_router,
+};
+use crate::application::web::routes::api::admin::users::users_router;
+use crate::context::MycologContext;
+use crate::secrets::{AdminScope, AdminToken};
+
+mod jobs;
+mod migrations;
+mod schedules;
+mod tokens;
+mod users;
+
+#[derive(Clone)]
+pub enum AdminStatus {
+    Authorized(AdminToken),
+    Unauthorized,
+}
+
+pub fn admin_router(context: &Arc<MycologContext>) -> Router<Arc<MycologContext>> {
+    Router::new()
+        .nest("/jobs", jobs_router(context))
+        .nest("/migrations", migrations_router(context))
+        .nest("/schedules", schedules_router(context))
+        .nest("/tokens", tokens_router(context))
+        .route_layer(Extension(AdminScope::Maintenance))
+        .nest(
+            "/users",
+            users_router(context).route_layer(Extension(AdminScope::UserManage)),
+        )
+}
+
+pub async fn authorize_admin(
+    State(context): State<Arc<MycologContext>>,
+    headers: HeaderMap,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let authorize_span = trace_span!("authorize_admin").entered();
+    let mut status = AdminStatus::Unauthorized;
+    if let Some(admin_header) = headers.get("admin") {
+        trace!(header = ?admin_header, "got admin header");
+        if let Ok(admin_token) = admin_header.to_str()
+            && let Some(admin_token) = context.secrets.admin.authenticate(admin_token)
+        {
+            if admin_token.is_expired() {
+                debug!(token = admin_token.name, "admin token is expired");
+            } else {
+                if should_record_admin_token_use(&context, &admin_token.name) {
+                    let context = Arc::clone(&context);
+                    let name = admin_token.name.clone();
+                    tokio::spawn(async move {
+                        if let Err(err) = record_admin_token_use(&context, &name).await {
+                            error!(?err, token = name, "unable to record admin token use");
+                        }
+                    });
+                }
+                status = AdminStatus::Authorized(admin_token);
+            }
+        }
+    }
+    drop(authorize_span);
+
+    req.extensions_mut().insert(status);
+    next.run(req).await
+}
@@ -0,0 +1,46 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::application::{Schedule, ScheduleAction};
+use crate::utils::serde::empty_string_as_none;
+
+#[derive(Serialize)]
+pub struct ScheduleSummary {
+    pub name: String,
+    /// Either `query` for schedule files or `job` for jobs implemented in Rust.
+    pub kind: &'static str,
+    pub cron: String,
+    pub timezone: String,
+    pub catch_up: bool,
+    pub next_run: Option<DateTime<Utc>>,
+}
+
+impl From<&Schedule> for ScheduleSummary {
+    fn from(schedule: &Schedule) -> Self {
+        Self {
+            name: schedule.name.clone(),
+            kind: match schedule.action {
+                ScheduleAction::Query(_) => "query",
+                ScheduleAction::Job(_) => "job",
+            },
+            cron: schedule.cron.as_str().to_string(),
+            timezone: schedule.timezone.name().to_string(),
+            catch_up: schedule.catch_up,
+            next_run: schedule.next_run_after(Utc::now()).ok(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RunOptions {
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub dry_run: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RunsOptions {
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub schedule: Option<String>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub limit: Option<u32>,
+}
@@ -0,0 +1,87 @@
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use tracing::{info, instrument, Level};
+
+use crate::application::database::system::Response;
+use crate::application::database::DatabaseRootAccess;
+use crate::application::web::error::{ResponseErrorExt, ResponseResult};
+use crate::application::web::routes::api::admin::schedules::data::{
+    RunOptions, RunsOptions, ScheduleSummary,
+};
+use crate::application::{recent_schedule_runs, run_schedule_now, ScheduleRun};
+use crate::context::MycologContext;
+
+mod data;
+
+pub fn schedules_router(context: &Arc<MycologContext>) -> Router<Arc<MycologContext>> {
+    Router::new()
+        .route("/", get(handle_schedules))
+        .route("/runs", get(handle_runs))
+        .route("/:name/run", post(handle_run))
+}
+
+async fn handle_schedules(
+    State(context): State<Arc<MycologContext>>,
+    _db: DatabaseRootAccess,
+) -> ResponseResult<Json<Vec<ScheduleSummary>>> {
+    let queries = context.schedules.borrow();
+    // Schedule files shadowed by a job of the same name are never run
+    let schedules = context
+        .jobs
+        .schedules()
+        .iter()
+        .chain(
+            queries
+                .schedules()
+                .iter()
+                .filter(|schedule| context.jobs.get(&schedule.name).is_none()),
+        )
+        .map(ScheduleSummary::from)
+        .collect();
+    Ok(Json(schedules))
+}
+
+/// Runs the schedule immediately. Dry runs do not change any data and are not recorded.
+#[instrument(level = Level::DEBUG, skip_all, fields(schedule = %name, dry_run = ?options.dry_run))]
+async fn handle_run(
+    State(context): State<Arc<MycologContext>>,
+    _db: DatabaseRootAccess,
+    Path(name): Path<String>,
+    Query(options): Query<RunOptions>,
+) -> ResponseResult<Json<Vec<Response>>> {
+    // Jobs take precedence over schedule files of the same name
+    let schedule = context
+        .jobs
+        .get(&name)
+        .cloned()
+        .or_else(|| context.schedules.borrow().get(&name).cloned())
+        .ok_or_else(|| {
+            anyhow!("schedule `{name}` does not exist").with_code(StatusCode::NOT_FOUND)
+        })?;
+    let dry_run = options.dry_run.unwrap_or(false);
+
+    let responses = run_schedule_now(&context, &schedule, dry_run)
+        .await
+        .map_err(|err| err.with_code(StatusCode::UNPROCESSABLE_ENTITY))?;
+    info!(schedule = name, dry_run, "manually ran schedule");
+    Ok(Json(responses))
+}
+
+/// Lists the most recent schedule runs, optionally filtered by schedule name.
+async fn handle_runs(
+    db: DatabaseRootAccess,
+    Query(options): Query<RunsOptions>,
+) -> ResponseResult<Json<Vec<ScheduleRun>>> {
+    let runs = recent_schedule_runs(
+        &db,
+        options.schedule.as_deref(),
+        options.limit.unwrap_or(50),
+    )
+    .await?;
+    Ok(Json(runs))
+}
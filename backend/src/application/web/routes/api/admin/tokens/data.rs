@@ -0,0 +1,21 @@
+use std::collections::BTreeSet;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::secrets::AdminScope;
+
+#[derive(Serialize, Deserialize)]
+pub struct AdminTokenSummary {
+    pub name: String,
+    pub scopes: BTreeSet<AdminScope>,
+    pub expires: Option<DateTime<Utc>>,
+    pub expired: bool,
+    pub time_last_used: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize)]
+pub struct AdminTokenUse {
+    pub name: String,
+    pub time_last_used: DateTime<Utc>,
+}
@@ -0,0 +1,76 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::extract::State;
+use axum::routing::get;
+use axum::{Json, Router};
+
+use crate::application::database::DatabaseRootAccess;
+use crate::application::web::error::ResponseResult;
+use crate::application::web::routes::api::admin::tokens::data::{AdminTokenSummary, AdminTokenUse};
+use crate::context::MycologContext;
+
+mod data;
+
+/// Uses of the same token are recorded at most once per interval, so not every request writes.
+const RECORD_USE_INTERVAL: Duration = Duration::from_secs(60);
+
+pub fn tokens_router(context: &Arc<MycologContext>) -> Router<Arc<MycologContext>> {
+    Router::new().route("/", get(handle_tokens))
+}
+
+/// Lists the configured admin tokens with their scopes and when they were last used, accurate to
+/// [RECORD_USE_INTERVAL].
+async fn handle_tokens(
+    State(context): State<Arc<MycologContext>>,
+    db: DatabaseRootAccess,
+) -> ResponseResult<Json<Vec<AdminTokenSummary>>> {
+    let uses: Vec<AdminTokenUse> = db
+        .query("SELECT meta::id(id) AS name, time_last_used FROM admin_token;")
+        .await?
+        .take(0)?;
+
+    let summaries = context
+        .secrets
+        .admin
+        .tokens()
+        .into_iter()
+        .map(|token| AdminTokenSummary {
+            time_last_used: uses
+                .iter()
+                .find(|token_use| token_use.name == token.name)
+                .map(|token_use| token_use.time_last_used),
+            expired: token.is_expired(),
+            name: token.name,
+            scopes: token.scopes,
+            expires: token.expires,
+        })
+        .collect();
+    Ok(Json(summaries))
+}
+
+/// Whether a use of the token has to be recorded, which is the case if no use was recorded
+/// within the last [RECORD_USE_INTERVAL].
+pub fn should_record_admin_token_use(context: &MycologContext, name: &str) -> bool {
+    let last_recorded_uses = context.admin_token_uses.lock().unwrap();
+    last_recorded_uses
+        .get(name)
+        .is_none_or(|last_recorded| last_recorded.elapsed() >= RECORD_USE_INTERVAL)
+}
+
+/// Records the use of the token, only a successful write restarts its [RECORD_USE_INTERVAL].
+pub async fn record_admin_token_use(context: &MycologContext, name: &str) -> anyhow::Result<()> {
+    context
+        .db
+        .auth_root()
+        .query("UPDATE type::thing('admin_token', $name) SET time_last_used = time::now();")
+        .bind("name", name)
+        .await?
+        .checked()?;
+    context
+        .admin_token_uses
+        .lock()
+        .unwrap()
+        .insert(name.to_string(), Instant::now());
+    Ok(())
+}
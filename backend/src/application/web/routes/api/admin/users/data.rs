@@ -0,0 +1,15 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+pub struct UserSummary {
+    pub email: String,
+    pub is_verified: bool,
+    pub is_locked: bool,
+    pub time_registered: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct UserOptions {
+    pub email: String,
+}
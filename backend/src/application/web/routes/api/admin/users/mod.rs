@@ -0,0 +1,67 @@
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use axum::extract::Query;
+use axum::http::StatusCode;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use tracing::{info, instrument, Level};
+
+use crate::application::database::DatabaseRootAccess;
+use crate::application::web::error::{ResponseErrorExt, ResponseResult};
+use crate::application::web::routes::api::admin::users::data::{UserOptions, UserSummary};
+use crate::context::MycologContext;
+
+mod data;
+
+pub fn users_router(context: &Arc<MycologContext>) -> Router<Arc<MycologContext>> {
+    Router::new()
+        .route("/", get(handle_users))
+        .route("/lock", post(handle_lock))
+        .route("/unlock", post(handle_unlock))
+}
+
+/// Lists all registered users, oldest first.
+async fn handle_users(db: DatabaseRootAccess) -> ResponseResult<Json<Vec<UserSummary>>> {
+    let users = db
+        .query("SELECT email, is_verified, is_locked, time_registered FROM user ORDER BY time_registered;")
+        .await?
+        .take(0)?;
+    Ok(Json(users))
+}
+
+/// Locks the user with the given email, which prevents them from signing in.
+#[instrument(level = Level::DEBUG, skip_all, fields(email = ?options.email))]
+async fn handle_lock(
+    db: DatabaseRootAccess,
+    Query(options): Query<UserOptions>,
+) -> ResponseResult<StatusCode> {
+    set_locked(&db, &options.email, true).await?;
+    info!(email = ?options.email, "locked user");
+    Ok(StatusCode::OK)
+}
+
+/// Unlocks the user with the given email, e.g. an unverified user locked by the hourly schedule.
+#[instrument(level = Level::DEBUG, skip_all, fields(email = ?options.email))]
+async fn handle_unlock(
+    db: DatabaseRootAccess,
+    Query(options): Query<UserOptions>,
+) -> ResponseResult<StatusCode> {
+    set_locked(&db, &options.email, false).await?;
+    info!(email = ?options.email, "unlocked user");
+    Ok(StatusCode::OK)
+}
+
+async fn set_locked(db: &DatabaseRootAccess, email: &str, locked: bool) -> ResponseResult<()> {
+    let updated: Vec<String> = db
+        .query("UPDATE user SET is_locked = $locked WHERE email = $email RETURN VALUE email;")
+        .bind("locked", locked)
+        .bind("email", email)
+        .await?
+        .checked()?
+        .take(0)?;
+    if updated.is_empty() {
+        return Err(anyhow!("user `{email}` does not exist").with_code(StatusCode::NOT_FOUND));
+    }
+    Ok(())
+}
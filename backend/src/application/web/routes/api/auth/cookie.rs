@@ -4,15 +4,15 @@ use axum_extra::extract::cookie::{Cookie, SameSite};
 
 use crate::application::database::system::AuthToken;
 
-pub fn build_auth_cookie(token: AuthToken, remember: bool) -> Cookie<'static> {
+pub fn build_auth_cookie(token: AuthToken, remember: bool, development: bool) -> Cookie<'static> {
     let mut builder = Cookie::build(("auth", token.to_insecure()))
         .secure(true)
         .http_only(true)
         .path("/api")
         .same_site(SameSite::None);
 
-    // Disable domain specification for dev env
-    if cfg!(not(feature = "dev-env")) {
+    // Disable domain specification for development setups
+    if !development {
         builder = builder.domain("mycolog.net").same_site(SameSite::Strict);
     }
 
@@ -4,16 +4,20 @@ use axum::Router;
 
 use crate::application::web::routes::api::auth::check::check_router;
 use crate::application::web::routes::api::auth::logout::logout_router;
+use crate::application::web::routes::api::auth::reset::reset_router;
 use crate::application::web::routes::api::auth::signin::signin_router;
 use crate::application::web::routes::api::auth::signup::signup_router;
+use crate::application::web::routes::api::auth::verify::verify_router;
 use crate::context::MycologContext;
 
 mod check;
 mod cookie;
 mod logout;
+mod reset;
 mod signin;
 mod signup;
 mod token;
+mod verify;
 
 pub fn auth_router(context: &Arc<MycologContext>) -> Router<Arc<MycologContext>> {
     Router::new()
@@ -21,4 +25,6 @@ pub fn auth_router(context: &Arc<MycologContext>) -> Router<Arc<MycologContext>>
         .nest("/signin", signin_router(context))
         .nest("/logout", logout_router(context))
         .nest("/check", check_router(context))
+        .nest("/verify", verify_router(context))
+        .nest("/reset", reset_router(context))
 }
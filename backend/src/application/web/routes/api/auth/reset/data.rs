@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ResetRequest {
+    pub email: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ResetConfirmation {
+    pub token: String,
+    pub password: String,
+}
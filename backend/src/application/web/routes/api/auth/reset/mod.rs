@@ -0,0 +1,197 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::post;
+use axum::{Json, Router};
+use axum_extra::extract::cookie::Cookie;
+use axum_extra::extract::CookieJar;
+use email_address_parser::EmailAddress;
+use surrealdb_core::sql;
+use tracing::{debug, error, info, instrument, Level};
+
+use crate::application::database::DatabaseRootAccess;
+use crate::application::email::Recipient;
+use crate::application::web::error::{ResponseErrorExt, ResponseResult};
+use crate::application::web::routes::api::auth::reset::data::{ResetConfirmation, ResetRequest};
+use crate::application::SendEmailPayload;
+use crate::context::MycologContext;
+
+mod data;
+
+/// Time after which an emailed reset token can no longer be used, expired resets are deleted by
+/// the hourly schedule.
+const RESET_LIFETIME: Duration = Duration::from_secs(30 * 60);
+/// Minimum time between two reset emails of the same user.
+const RESET_COOLDOWN: Duration = Duration::from_secs(5 * 60);
+
+pub fn reset_router(state: &Arc<MycologContext>) -> Router<Arc<MycologContext>> {
+    Router::new()
+        .route("/request", post(handle_request))
+        .route("/confirm", post(handle_confirm))
+}
+
+/// Always succeeds, whether an account with the email exists is not revealed. The reset is
+/// processed in the background, so the response time does not reveal it either.
+#[instrument(level = Level::DEBUG, skip_all, fields(email = ? request.email))]
+async fn handle_request(
+    State(context): State<Arc<MycologContext>>,
+    Json(request): Json<ResetRequest>,
+) -> ResponseResult<StatusCode> {
+    if !EmailAddress::is_valid(&request.email, None) {
+        return Err(
+            anyhow!("given email is no valid email addresse").with_code(StatusCode::BAD_REQUEST)
+        );
+    }
+
+    debug!("received password reset request");
+    tokio::spawn(async move {
+        if let Err(err) = send_reset_link(&context, &request.email).await {
+            error!(?err, recipient = %request.email, "unable to send password reset email");
+        }
+    });
+
+    Ok(StatusCode::OK)
+}
+
+#[instrument(level = Level::DEBUG, skip_all)]
+async fn handle_confirm(
+    State(context): State<Arc<MycologContext>>,
+    jar: CookieJar,
+    Json(confirmation): Json<ResetConfirmation>,
+) -> ResponseResult<CookieJar> {
+    if confirmation.password.is_empty() {
+        return Err(anyhow!("new password is empty").with_code(StatusCode::BAD_REQUEST));
+    }
+
+    let email = confirm_reset(
+        &context.db.auth_root(),
+        &confirmation.token,
+        &confirmation.password,
+    )
+    .await?
+    .ok_or(anyhow!("reset token is invalid or expired").with_code(StatusCode::NOT_FOUND))?;
+    info!(?email, "reset password, existing sessions are revoked");
+
+    Ok(jar.remove(Cookie::from("auth")))
+}
+
+/// Sets the new password of the user the reset with the given token belongs to and revokes all
+/// their sessions, returns their email.
+async fn confirm_reset(
+    db: &DatabaseRootAccess,
+    token: &str,
+    password: &str,
+) -> anyhow::Result<Option<String>> {
+    // The transaction consumes the reset exactly once, concurrent confirmations conflict and fail.
+    // The password field hashes the new password with argon2
+    Ok(db
+        .query(
+            "BEGIN TRANSACTION;
+            LET $reset = (SELECT * FROM ONLY password_reset WHERE token_hash = crypto::sha256($token) AND time_created > time::now() - $lifetime LIMIT 1);
+            DELETE password_reset WHERE $reset != NONE AND user = $reset.user;
+            UPDATE user SET password = $password, time_password_changed = time::now(), session_generation += 1 WHERE $reset != NONE AND id = $reset.user RETURN VALUE email;
+            COMMIT TRANSACTION;",
+        )
+        .bind("token", token)
+        .bind("lifetime", sql::Duration::from(RESET_LIFETIME))
+        .bind("password", password)
+        .await?
+        .checked()?
+        .take::<Vec<String>>(2)?
+        .pop())
+}
+
+/// Replaces any pending reset of the user with the given email by a new one and enqueues the
+/// email containing its token. Does nothing if no such user exists or a reset was requested
+/// recently.
+async fn send_reset_link(context: &MycologContext, email: &str) -> anyhow::Result<()> {
+    let token = context
+        .db
+        .auth_root()
+        .query(
+            "BEGIN TRANSACTION;
+            LET $user = (SELECT VALUE id FROM ONLY user WHERE email = $email LIMIT 1);
+            LET $recent = array::len(SELECT id FROM password_reset WHERE user = $user AND time_created > time::now() - $cooldown);
+            LET $token = rand::string(48);
+            IF $user != NONE AND $recent = 0 {
+                DELETE password_reset WHERE user = $user;
+                CREATE password_reset SET user = $user, token_hash = crypto::sha256($token);
+                RETURN $token;
+            };
+            COMMIT TRANSACTION;",
+        )
+        .bind("email", email)
+        .bind("cooldown", sql::Duration::from(RESET_COOLDOWN))
+        .await?
+        .checked()?
+        .take::<Option<String>>(3)?;
+    let Some(token) = token else {
+        debug!(recipient = %email, "no password reset for unknown or recently reset account");
+        return Ok(());
+    };
+
+    let link = format!("{}/reset/?token={token}", context.config.web_public_url);
+    context
+        .queue
+        .enqueue(
+            "send_email",
+            SendEmailPayload {
+                email_type: "reset".to_string(),
+                subject: "Reset your Mycolog Password".to_string(),
+                recipients: vec![Recipient::new(email)
+                    .bind("email_addresse", email)
+                    .bind("link", link)],
+            },
+        )
+        .await?;
+    info!(recipient = %email, "sent password reset email");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::application::DatabaseSystem;
+
+    #[tokio::test]
+    async fn reset_revokes_sessions_of_the_same_second() {
+        let system = DatabaseSystem::create_migrated_in_memory().await.unwrap();
+        let old_credentials = json!({ "email": "user@example.com", "password": "old password" });
+        let new_credentials = json!({ "email": "user@example.com", "password": "new password" });
+        let signup_token = system.signup("user", &old_credentials).await.unwrap();
+        let signin_token = system.signin("user", &old_credentials).await.unwrap();
+        system.auth_token(signin_token.clone()).await.unwrap();
+
+        let db = system.auth_root();
+        db.query("CREATE password_reset SET user = (SELECT VALUE id FROM ONLY user WHERE email = $email LIMIT 1), token_hash = crypto::sha256('reset-token');")
+            .bind("email", "user@example.com")
+            .await
+            .unwrap()
+            .checked()
+            .unwrap();
+        assert_eq!(
+            confirm_reset(&db, "reset-token", "new password")
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("user@example.com")
+        );
+
+        // Generations are compared exactly, so sessions issued in the second of the reset are revoked
+        assert!(system.auth_token(signup_token).await.is_err());
+        assert!(system.auth_token(signin_token).await.is_err());
+        assert!(system.signin("user", &old_credentials).await.is_err());
+        let new_token = system.signin("user", &new_credentials).await.unwrap();
+        system.auth_token(new_token).await.unwrap();
+        assert!(confirm_reset(&db, "reset-token", "other password")
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
@@ -43,6 +43,10 @@ async fn handle_signin(
         .map_err(|err| err.with_code(StatusCode::UNAUTHORIZED))?;
     info!(email = ?credentials.email, "approved signin request");
 
-    let cookie = build_auth_cookie(token, options.remember.unwrap_or(false));
+    let cookie = build_auth_cookie(
+        token,
+        options.remember.unwrap_or(false),
+        context.config.development,
+    );
     Ok(jar.add(cookie))
 }
@@ -10,10 +10,10 @@ use reqwest::StatusCode;
 use serde_json::Value;
 use tracing::{debug, error, info, instrument, Level};
 
-use crate::application::email::Recipient;
 use crate::application::web::error::{ResponseErrorExt, ResponseResult};
 use crate::application::web::routes::api::auth::cookie::build_auth_cookie;
 use crate::application::web::routes::api::auth::signup::data::SignupCredentials;
+use crate::application::web::routes::api::auth::verify::send_verification_link;
 use crate::context::MycologContext;
 
 mod data;
@@ -41,16 +41,12 @@ async fn handle_signup(
         .signup("user", credentials.clone())
         .await
         .map_err(|err| err.with_code(StatusCode::UNAUTHORIZED))?;
-    /*tokio::spawn(async move {
-        if let Err(err) = context.email.sumbit_email(
-            "verify",
-            "Verify your Mycolog Account",
-            vec![Recipient::new(&email).bind("email_addresse", &email)]).await {
-            error!(?err, recipient = %email, "unable to submit verification email");
-        }
-    });*/
+    // The account exists at this point, a failing email must not fail the signup
+    if let Err(err) = send_verification_link(&context, &email).await {
+        error!(?err, recipient = %email, "unable to send verification email");
+    }
     info!(email = ?credentials.email, "approved signup request");
 
-    let cookie = build_auth_cookie(token, false);
+    let cookie = build_auth_cookie(token, false, context.config.development);
     Ok(jar.add(cookie))
 }
@@ -0,0 +1,7 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct VerificationStatus {
+    pub email: String,
+    pub is_verified: bool,
+}
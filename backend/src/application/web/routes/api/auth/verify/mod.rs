@@ -0,0 +1,193 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::Redirect;
+use axum::routing::{get, post};
+use axum::Router;
+use surrealdb_core::sql;
+use tracing::{debug, info, instrument, Level};
+
+use crate::application::database::system::DatabaseScopeAccess;
+use crate::application::database::DatabaseRootAccess;
+use crate::application::email::Recipient;
+use crate::application::web::error::{ResponseErrorExt, ResponseResult};
+use crate::application::web::routes::api::auth::verify::data::VerificationStatus;
+use crate::application::SendEmailPayload;
+use crate::context::MycologContext;
+
+mod data;
+
+/// Minimum time between two verification emails of the same user.
+const RESEND_COOLDOWN: Duration = Duration::from_secs(5 * 60);
+
+pub fn verify_router(state: &Arc<MycologContext>) -> Router<Arc<MycologContext>> {
+    Router::new()
+        .route("/resend", post(handle_resend))
+        .route("/:token", get(handle_verify))
+}
+
+#[instrument(level = Level::DEBUG, skip_all)]
+async fn handle_verify(
+    State(context): State<Arc<MycologContext>>,
+    Path(token): Path<String>,
+) -> ResponseResult<Redirect> {
+    let email = verify_account(&context.db.auth_root(), &token)
+        .await?
+        .ok_or(
+            anyhow!("verification link is invalid or expired").with_code(StatusCode::NOT_FOUND),
+        )?;
+    info!(?email, "verified account");
+
+    Ok(Redirect::to("/"))
+}
+
+/// Verifies and unlocks the account the link with the given token belongs to, returns its email.
+/// Links expire after a day, like the hourly cleanup assumes.
+async fn verify_account(db: &DatabaseRootAccess, token: &str) -> anyhow::Result<Option<String>> {
+    Ok(db
+        .query(
+            "LET $user = (SELECT VALUE user FROM ONLY verification_link WHERE token = $token AND time_created > time::now() - 1d LIMIT 1);
+            UPDATE user SET is_verified = true, is_locked = false WHERE $user != NONE AND id = $user RETURN VALUE email;
+            DELETE verification_link WHERE $user != NONE AND user = $user;",
+        )
+        .bind("token", token)
+        .await?
+        .checked()?
+        .take::<Vec<String>>(1)?
+        .pop())
+}
+
+#[instrument(level = Level::DEBUG, skip_all)]
+async fn handle_resend(
+    State(context): State<Arc<MycologContext>>,
+    db: DatabaseScopeAccess,
+) -> ResponseResult<StatusCode> {
+    let status = db
+        .query("SELECT email, is_verified FROM ONLY $auth.id;")
+        .await
+        .and_then(|mut response| response.take::<Option<VerificationStatus>>(0))
+        .and_then(|status| status.ok_or(anyhow!("authorized account does not exist")))
+        .map_err(|err| {
+            err.context("cannot retrieve email from authorized account")
+                .with_code(StatusCode::UNAUTHORIZED)
+        })?;
+    debug!(email = ?status.email, "received verification resend request");
+
+    if status.is_verified {
+        return Err(anyhow!("account is already verified").with_code(StatusCode::CONFLICT));
+    }
+    // Checking the cooldown and creating the link in one transaction keeps concurrent requests
+    // from both passing the check
+    let token = context
+        .db
+        .auth_root()
+        .query(
+            "BEGIN TRANSACTION;
+            LET $user = (SELECT VALUE id FROM ONLY user WHERE email = $email LIMIT 1);
+            LET $recent = array::len(SELECT id FROM verification_link WHERE user = $user AND time_created > time::now() - $cooldown);
+            IF $recent = 0 {
+                LET $link = fn::create_verification_link($user);
+                RETURN $link.token;
+            };
+            COMMIT TRANSACTION;",
+        )
+        .bind("email", &status.email)
+        .bind("cooldown", sql::Duration::from(RESEND_COOLDOWN))
+        .await?
+        .checked()?
+        .take::<Option<String>>(2)?
+        .ok_or(
+            anyhow!("verification email was sent too recently, try again later")
+                .with_code(StatusCode::TOO_MANY_REQUESTS),
+        )?;
+
+    enqueue_verification_email(&context, &status.email, &token).await?;
+    info!(email = ?status.email, "resent verification email");
+
+    Ok(StatusCode::OK)
+}
+
+/// Creates a new verification link for the user with the given email and enqueues the
+/// verification email containing it.
+pub async fn send_verification_link(context: &MycologContext, email: &str) -> anyhow::Result<()> {
+    let token = context
+        .db
+        .auth_root()
+        .query(
+            "LET $link = fn::create_verification_link((SELECT VALUE id FROM ONLY user WHERE email = $email LIMIT 1));
+            RETURN $link.token;",
+        )
+        .bind("email", email)
+        .await?
+        .checked()?
+        .take::<Option<String>>(1)?
+        .ok_or(anyhow!("no verification link was created for `{email}`"))?;
+
+    enqueue_verification_email(context, email, &token).await
+}
+
+async fn enqueue_verification_email(
+    context: &MycologContext,
+    email: &str,
+    token: &str,
+) -> anyhow::Result<()> {
+    let link = format!("{}/api/auth/verify/{token}", context.config.web_public_url);
+    context
+        .queue
+        .enqueue(
+            "send_email",
+            SendEmailPayload {
+                email_type: "verify".to_string(),
+                subject: "Verify your Mycolog Account".to_string(),
+                recipients: vec![Recipient::new(email)
+                    .bind("email_addresse", email)
+                    .bind("link", link)],
+            },
+        )
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::application::DatabaseSystem;
+
+    #[tokio::test]
+    async fn signin_after_verification_and_lock() {
+        let system = DatabaseSystem::create_migrated_in_memory().await.unwrap();
+        let credentials = json!({ "email": "user@example.com", "password": "correct horse" });
+        system.signup("user", &credentials).await.unwrap();
+
+        let db = system.auth_root();
+        let token = db
+            .query(
+                "LET $link = fn::create_verification_link((SELECT VALUE id FROM ONLY user WHERE email = $email LIMIT 1));
+                RETURN $link.token;",
+            )
+            .bind("email", "user@example.com")
+            .await
+            .unwrap()
+            .take::<Option<String>>(1)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            verify_account(&db, &token).await.unwrap().as_deref(),
+            Some("user@example.com")
+        );
+        system.signin("user", &credentials).await.unwrap();
+
+        // Other updates of the user, like the hourly lock, keep the password as well
+        db.query("UPDATE user SET is_locked = true;")
+            .await
+            .unwrap()
+            .checked()
+            .unwrap();
+        system.signin("user", &credentials).await.unwrap();
+        assert!(verify_account(&db, &token).await.unwrap().is_none());
+    }
+}
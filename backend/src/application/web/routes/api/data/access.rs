@@ -11,6 +11,7 @@ use crate::application::database::DatabaseRootAccess;
 use crate::application::web::error::{ResponseError, ResponseErrorExt};
 use crate::application::web::routes::api::admin::AdminStatus;
 use crate::context::MycologContext;
+use crate::secrets::AdminScope;
 
 #[async_trait]
 impl FromRequestParts<Arc<MycologContext>> for DatabaseScopeAccess {
@@ -25,7 +26,9 @@ impl FromRequestParts<Arc<MycologContext>> for DatabaseScopeAccess {
             .get::<AdminStatus>()
             .cloned()
             .unwrap_or(AdminStatus::Unauthorized);
-        if let AdminStatus::Authorized = admin_status {
+        if let AdminStatus::Authorized(token) = admin_status
+            && token.has_scope(AdminScope::QueryRoot)
+        {
             return Ok(state.db.auth_root().into_scoped());
         }
 
@@ -51,10 +54,25 @@ impl FromRequestParts<Arc<MycologContext>> for DatabaseRootAccess {
             .get::<AdminStatus>()
             .cloned()
             .unwrap_or(AdminStatus::Unauthorized);
-        if let AdminStatus::Authorized = admin_status {
-            return Ok(state.db.auth_root());
+        let AdminStatus::Authorized(token) = admin_status else {
+            return Err(anyhow!("unable to authorize admin").with_code(StatusCode::UNAUTHORIZED));
+        };
+
+        // Routes declare the scope they require, routes without one are not accessible
+        let Some(scope) = parts.extensions.get::<AdminScope>().copied() else {
+            return Err(
+                anyhow!("route does not declare an admin scope").with_code(StatusCode::FORBIDDEN)
+            );
+        };
+        if !token.has_scope(scope) {
+            return Err(anyhow!(
+                "admin token `{}` lacks scope `{}`",
+                token.name,
+                scope.as_str()
+            )
+            .with_code(StatusCode::FORBIDDEN));
         }
 
-        return Err(anyhow!("unable to authorize admin").with_code(StatusCode::UNAUTHORIZED));
+        Ok(state.db.auth_root())
     }
 }
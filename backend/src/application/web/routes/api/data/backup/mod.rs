@@ -1,28 +1,123 @@
+use std::io;
 use std::sync::Arc;
 
 use axum::body::Body;
-use axum::http::{header, HeaderMap};
+use axum::extract::State;
+use axum::http::{header, HeaderMap, StatusCode};
 use axum::response::{IntoResponse, Response};
-use axum::routing::get;
-use axum::Router;
-use tokio_util::io::ReaderStream;
+use axum::routing::{get, post};
+use axum::{Extension, Json, Router};
+use chrono::Local;
+use futures_lite::{stream, StreamExt};
+use tokio::sync::oneshot;
+use tokio_util::io::{ReaderStream, StreamReader};
+use tracing::{error, info, instrument, Level};
 
 use crate::application::database::DatabaseRootAccess;
-use crate::application::web::error::ResponseResult;
+use crate::application::web::error::{ResponseErrorExt, ResponseResult};
+use crate::application::{
+    recent_backup_verifications, restore_backup_archive, write_backup_archive, BackupVerification,
+};
 use crate::context::MycologContext;
+use crate::secrets::AdminScope;
 
 pub fn backup_router(context: &Arc<MycologContext>) -> Router<Arc<MycologContext>> {
-    Router::new().route("/", get(handle_backup))
+    Router::new()
+        .route(
+            "/",
+            get(handle_backup).layer(Extension(AdminScope::BackupRead)),
+        )
+        .route(
+            "/restore",
+            post(handle_restore).layer(Extension(AdminScope::Restore)),
+        )
+        .route(
+            "/verifications",
+            get(handle_verifications).layer(Extension(AdminScope::BackupRead)),
+        )
 }
 
-async fn handle_backup(db: DatabaseRootAccess) -> ResponseResult<Response> {
-    let compressed_reader = db.backup().await?;
+async fn handle_backup(
+    State(context): State<Arc<MycologContext>>,
+    db: DatabaseRootAccess,
+) -> ResponseResult<Response> {
+    let backup_key = context.secrets.backup.key();
+    let (archive_writer, archive_reader) = tokio::io::duplex(64 * 1024);
+    let (archive_result, archive_failed) = oneshot::channel();
+    tokio::spawn(async move {
+        let result = write_backup_archive(
+            &db,
+            &context.config.images_dir,
+            backup_key.as_ref(),
+            archive_writer,
+        )
+        .await;
+        if let Err(err) = &result {
+            error!(?err, "streaming backup archive failed");
+        }
+        let _ = archive_result.send(result.is_ok());
+    });
+    // The status is already sent once the archive fails, so the body ends with an error instead.
+    // This aborts the response and the client never receives a truncated archive as complete.
+    let archive_outcome = stream::once_future(async move {
+        match archive_failed.await {
+            Ok(true) => None,
+            Ok(false) | Err(_) => Some(Err(io::Error::new(
+                io::ErrorKind::Other,
+                "streaming backup archive failed",
+            ))),
+        }
+    })
+    .filter_map(|outcome| outcome);
 
+    let time = Local::now().format("%Y%m%d%H%M%S").to_string();
     let mut headers = HeaderMap::new();
-    headers.insert(header::CONTENT_TYPE, "application/brotli".parse()?);
+    let content_type = match backup_key {
+        Some(_) => "application/octet-stream",
+        None => "application/brotli",
+    };
+    headers.insert(header::CONTENT_TYPE, content_type.parse()?);
+    headers.insert(
+        header::CONTENT_DISPOSITION,
+        format!("attachment; filename=\"backup_{time}.tar.br\"").parse()?,
+    );
     Ok((
         headers,
-        Body::from_stream(ReaderStream::new(compressed_reader)),
+        Body::from_stream(ReaderStream::new(archive_reader).chain(archive_outcome)),
     )
         .into_response())
 }
+
+#[instrument(level = Level::DEBUG, skip_all)]
+async fn handle_restore(
+    State(context): State<Arc<MycologContext>>,
+    db: DatabaseRootAccess,
+    body: Body,
+) -> ResponseResult<StatusCode> {
+    let compressed_reader = StreamReader::new(
+        body.into_data_stream()
+            .map(|chunk| chunk.map_err(|err| io::Error::new(io::ErrorKind::Other, err))),
+    );
+
+    restore_backup_archive(
+        &db,
+        &context.config.images_dir,
+        context.secrets.backup.key().as_ref(),
+        compressed_reader,
+    )
+    .await
+    .map_err(|err| err.with_code(StatusCode::UNPROCESSABLE_ENTITY))?;
+    info!("restored uploaded backup");
+
+    // Remove images which are not part of the restored backup
+    context.images.clean().await?;
+
+    Ok(StatusCode::OK)
+}
+
+/// Lists the results of the most recent backup verifications, so corrupt backups are noticed.
+async fn handle_verifications(
+    db: DatabaseRootAccess,
+) -> ResponseResult<Json<Vec<BackupVerification>>> {
+    Ok(Json(recent_backup_verifications(&db, 50).await?))
+}
@@ -36,6 +36,10 @@ impl<S: AsRef<MycologSecrets> + Send + Sync, E: FromRequest<S, Rejection: IntoRe
     type Rejection = ResponseError;
 
     async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let signing_key = state.as_ref().keys.mailersend_webhook().ok_or(
+            anyhow!("webhook is disabled because no signing key is configured")
+                .with_code(StatusCode::SERVICE_UNAVAILABLE),
+        )?;
         let (req_parts, req_body) = req.into_parts();
         let signature_str = req_parts
             .headers
@@ -55,12 +59,10 @@ impl<S: AsRef<MycologSecrets> + Send + Sync, E: FromRequest<S, Rejection: IntoRe
                 .await
                 .map_err(ResponseError::from_response)?;
 
-        let mut hmac =
-            HmacSha256::new_from_slice(state.as_ref().keys.mailersend_webhook().as_bytes())
-                .map_err(|err| {
-                    anyhow!("server defined invalid signing key: {:?}", err)
-                        .with_code(StatusCode::INTERNAL_SERVER_ERROR)
-                })?;
+        let mut hmac = HmacSha256::new_from_slice(signing_key.as_bytes()).map_err(|err| {
+            anyhow!("server defined invalid signing key: {:?}", err)
+                .with_code(StatusCode::INTERNAL_SERVER_ERROR)
+        })?;
         hmac.update(&body_bytes);
         hmac.verify_slice(&expected_signature).map_err(|err| {
             anyhow!("`signature` header does not match content: {:?}", err)
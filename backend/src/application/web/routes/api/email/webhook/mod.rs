@@ -10,7 +10,7 @@ use tracing::{debug, Level};
 use tracing::{info, instrument};
 
 use crate::application::email::events::EmailWebhookEvent;
-use crate::application::web::error::ResponseResult;
+use crate::application::web::error::{ResponseErrorExt, ResponseResult};
 use crate::application::web::routes::api::email::webhook::extractors::Signed;
 use crate::context::MycologContext;
 
@@ -23,9 +23,12 @@ pub fn email_webhook_router(context: &Arc<MycologContext>) -> Router<Arc<Mycolog
 
 async fn handle_webhook_request(
     State(context): State<Arc<MycologContext>>,
-    Signed(event): Signed<EmailWebhookEvent>,
+    Signed(Json(payload)): Signed<Json<Value>>,
 ) -> ResponseResult<StatusCode> {
+    let event = EmailWebhookEvent::try_from(payload.clone())
+        .map_err(|err| err.with_code(StatusCode::UNPROCESSABLE_ENTITY))?;
     debug!(?event, "received email webhook event");
-    tokio::spawn(async move { context.email.process(event).await });
+    // Processed by the job queue, so the event is not lost if processing fails
+    context.queue.enqueue("email_webhook", payload).await?;
     Ok(StatusCode::OK)
 }
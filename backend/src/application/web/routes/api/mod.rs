@@ -4,7 +4,7 @@ use axum::http::HeaderValue;
 use axum::{middleware, Router};
 use tower_http::cors::{AllowCredentials, AllowHeaders, CorsLayer};
 
-use crate::application::web::routes::api::admin::authorize_admin;
+use crate::application::web::routes::api::admin::{admin_router, authorize_admin};
 use crate::application::web::routes::api::auth::auth_router;
 use crate::application::web::routes::api::data::data_router;
 use crate::context::MycologContext;
@@ -21,13 +21,14 @@ pub fn api_router(context: &Arc<MycologContext>) -> Router<Arc<MycologContext>>
         .nest("/email", email_router(context))
         .nest("/auth", auth_router(context))
         .nest("/data", data_router(context))
+        .nest("/admin", admin_router(context))
         .route_layer(middleware::from_fn_with_state(
             Arc::clone(context),
             authorize_admin,
         ));
 
-    if cfg!(feature = "dev-env") {
-        // Enable cors support in dev environment for seperate frontend
+    if context.config.development {
+        // Enable cors support in development setups for seperate frontend
         router = router.route_layer(CorsLayer::very_permissive())
     }
 
@@ -21,5 +21,8 @@ pub fn try_build_routes(
 fn root_router(context: &Arc<MycologContext>) -> Router<Arc<MycologContext>> {
     Router::new()
         .nest("/api", api_router(context))
-        .fallback_service(ServeDir::new("site").fallback(ServeFile::new("site/404.html")))
+        .fallback_service(
+            ServeDir::new(&context.config.site_dir)
+                .fallback(ServeFile::new(context.config.site_dir.join("404.html"))),
+        )
 }
@@ -1,8 +1,11 @@
 use std::net::IpAddr;
+use std::path::PathBuf;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use tracing::{debug, info};
 
+use crate::secrets::AdminScope;
+
 pub fn parse_arguments() -> MycologArguments {
     debug!("Parsing CLI arguments...");
     let arguments = MycologArguments::parse();
@@ -14,9 +17,83 @@ pub fn parse_arguments() -> MycologArguments {
 #[command(version, about)]
 pub struct MycologArguments {
     /// The port to listen on.
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     pub port: Option<u16>,
     /// The host to bind the server to.
-    #[arg(short = 'i', long, value_parser)]
+    #[arg(short = 'i', long, value_parser, global = true)]
     pub hostname: Option<IpAddr>,
+    /// The config file to read, defaults to `config/config.toml`. A `.local.toml` file next to it
+    /// overrides its values.
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+    /// Never write missing config values back to the config file, e.g. for read-only mounts.
+    #[arg(long, global = true)]
+    pub read_only_config: bool,
+    /// The directory all relative paths are resolved against, defaults to the current directory.
+    #[arg(short = 'w', long, global = true)]
+    pub workdir: Option<PathBuf>,
+    /// The action to perform, defaults to running the server.
+    #[command(subcommand)]
+    pub command: Option<MycologCommand>,
+}
+
+impl MycologArguments {
+    pub fn command(&self) -> MycologCommand {
+        self.command.clone().unwrap_or(MycologCommand::Serve)
+    }
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum MycologCommand {
+    /// Runs the web server and all background services.
+    Serve,
+    #[command(flatten)]
+    Maintenance(MaintenanceCommand),
+}
+
+/// Commands which run without starting any of the application services.
+#[derive(Clone, Debug, Subcommand)]
+pub enum MaintenanceCommand {
+    /// Applies pending database migrations.
+    Migrate {
+        /// Only report the applied, pending and drifted migrations without applying them.
+        #[arg(long)]
+        status: bool,
+        /// Apply the pending migrations to an in-memory copy of the database to validate them.
+        #[arg(long, conflicts_with = "status")]
+        dry_run: bool,
+    },
+    /// Reverts the most recently applied database migrations using their `.down.surql` files.
+    Rollback {
+        /// The amount of migrations to revert.
+        #[arg(short, long, default_value_t = 1)]
+        count: u32,
+    },
+    /// Writes a compressed database backup to the given file.
+    Backup {
+        /// The file the backup is written to.
+        #[arg(short, long)]
+        out: PathBuf,
+    },
+    /// Imports a compressed database backup from the given file.
+    Restore {
+        /// The backup file to import.
+        file: PathBuf,
+    },
+    /// Validates the configuration and secrets files and reports every problem found.
+    CheckConfig,
+    /// Generates a new admin token and prints it along with the entry for secrets/admin.toml.
+    GenerateAdminToken {
+        /// The unique name of the token.
+        name: String,
+        /// The scopes granted to the token, e.g. `backup-read`.
+        #[arg(short, long = "scope", required = true)]
+        scopes: Vec<AdminScope>,
+    },
+    /// Writes the JSON schema of the configuration file, e.g. for completion in editors.
+    ConfigSchema {
+        /// The file the schema is written to.
+        #[arg(short, long, default_value = "config/config.schema.json")]
+        out: PathBuf,
+    },
 }
@@ -0,0 +1,34 @@
+use std::path::PathBuf;
+
+use anyhow::anyhow;
+use tracing::info;
+
+use crate::application::write_backup_archive;
+use crate::cli::MycologArguments;
+use crate::commands::open_root_database;
+
+pub async fn backup_command(out: PathBuf, arguments: MycologArguments) -> anyhow::Result<()> {
+    let (db, config, secrets) = open_root_database(arguments).await?;
+
+    let target_file = tokio::fs::File::create(&out)
+        .await
+        .map_err(|err| anyhow!("unable to create {}: {:?}", out.display(), err))?;
+    let written = write_backup_archive(
+        &db,
+        &config.images_dir,
+        secrets.backup.key().as_ref(),
+        target_file,
+    )
+    .await;
+    let manifest = match written {
+        Ok(manifest) => manifest,
+        Err(err) => {
+            // Never leave a truncated backup behind
+            let _ = tokio::fs::remove_file(&out).await;
+            return Err(err);
+        }
+    };
+
+    info!(file = %out.display(), images = manifest.images.len(), "backup written");
+    Ok(())
+}
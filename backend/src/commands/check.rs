@@ -0,0 +1,39 @@
+use std::path::PathBuf;
+
+use anyhow::anyhow;
+use tracing::info;
+
+use crate::cli::MycologArguments;
+use crate::config::{config_json_schema, try_parse_config, ConfigReport};
+use crate::secrets::try_parse_secrets;
+
+pub fn check_config_command(mut arguments: MycologArguments) -> anyhow::Result<()> {
+    // Checking has to leave the config files untouched
+    arguments.read_only_config = true;
+    let config = try_parse_config(arguments).map_err(|err| {
+        if let Some(report) = err.downcast_ref::<ConfigReport>() {
+            report.log();
+            return anyhow!(
+                "config is invalid, found {} problems",
+                report.problems.len()
+            );
+        }
+        anyhow!("config is invalid: {err}")
+    })?;
+    info!(?config, "config is valid");
+
+    let secrets =
+        try_parse_secrets(&config).map_err(|err| anyhow!("secrets are invalid: {err}"))?;
+    info!(?secrets, "secrets are valid");
+
+    Ok(())
+}
+
+pub fn config_schema_command(out: PathBuf) -> anyhow::Result<()> {
+    let schema = serde_json::to_string_pretty(&config_json_schema())?;
+    std::fs::write(&out, schema)
+        .map_err(|err| anyhow!("unable to write {}: {:?}", out.display(), err))?;
+
+    info!(file = %out.display(), "config schema written");
+    Ok(())
+}
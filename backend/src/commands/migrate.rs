@@ -0,0 +1,62 @@
+use tracing::{info, warn};
+
+use crate::application::{ImageManager, MigrationDrift, MigrationManager};
+use crate::cli::MycologArguments;
+use crate::commands::open_root_database;
+
+pub async fn migrate_command(
+    status: bool,
+    dry_run: bool,
+    arguments: MycologArguments,
+) -> anyhow::Result<()> {
+    let (db, config, _) = open_root_database(arguments).await?;
+    let manager = MigrationManager::new(&config.migrations_dir).await?;
+
+    if status {
+        let plan = manager.plan(&db).await?;
+        info!(schema_version = ?plan.schema_version, "read database schema version");
+        for file_name in &plan.applied {
+            info!(file = %file_name, "migration is applied");
+        }
+        if plan.pending.is_empty() {
+            info!("database is up to date, no pending migrations");
+        }
+        for file_name in &plan.pending {
+            info!(file = %file_name, "migration is pending");
+        }
+        for drift in &plan.drifted {
+            match drift {
+                MigrationDrift::Modified { file_name, .. } => {
+                    warn!(file = %file_name, "migration has been modified after it was applied")
+                }
+                MigrationDrift::Missing { file_name } => {
+                    warn!(file = %file_name, "applied migration is missing on file system")
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    if dry_run {
+        let validated = manager.dry_run_migrations(&db).await?;
+        for file_name in &validated {
+            info!(file = %file_name, "migration applies cleanly");
+        }
+        info!(
+            "validated {} migrations against a copy of the database",
+            validated.len()
+        );
+        return Ok(());
+    }
+
+    let images = ImageManager::new(
+        &config.images_dir,
+        db.clone(),
+        config.images_max_bytes_per_user,
+    )?;
+    let migrated_count = manager
+        .apply_migrations(&db, Some(&images), config.migration_drift_policy)
+        .await?;
+    info!("applied {} migrations", migrated_count);
+    Ok(())
+}
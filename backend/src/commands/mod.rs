@@ -0,0 +1,69 @@
+use tracing::{error, info};
+
+use crate::application::{open_database_system, DatabaseRootAccess};
+use crate::cli::{MaintenanceCommand, MycologArguments};
+use crate::commands::backup::backup_command;
+use crate::commands::check::{check_config_command, config_schema_command};
+use crate::commands::migrate::migrate_command;
+use crate::commands::restore::restore_command;
+use crate::commands::rollback::rollback_command;
+use crate::commands::token::generate_admin_token_command;
+use crate::config::{try_parse_config, MycologConfig};
+use crate::secrets::{try_parse_secrets, MycologSecrets};
+use crate::startup::directories::prepare_application_dirs;
+use crate::startup::logging::setup_logging;
+
+mod backup;
+mod check;
+mod migrate;
+mod restore;
+mod rollback;
+mod token;
+
+/// Runs a maintenance command without starting any of the application services.
+/// Intended to be used against a stopped instance.
+pub async fn run_command(command: MaintenanceCommand, arguments: MycologArguments) -> i32 {
+    let _logging = match setup_logging(&arguments) {
+        Ok(logging) => Some(logging),
+        Err(err) => {
+            eprintln!("unable to setup logging: {err}");
+            None
+        }
+    };
+
+    let result = match command {
+        MaintenanceCommand::CheckConfig => check_config_command(arguments),
+        MaintenanceCommand::ConfigSchema { out } => config_schema_command(out),
+        MaintenanceCommand::GenerateAdminToken { name, scopes } => {
+            generate_admin_token_command(name, scopes)
+        }
+        MaintenanceCommand::Migrate { status, dry_run } => {
+            migrate_command(status, dry_run, arguments).await
+        }
+        MaintenanceCommand::Rollback { count } => rollback_command(count, arguments).await,
+        MaintenanceCommand::Backup { out } => backup_command(out, arguments).await,
+        MaintenanceCommand::Restore { file } => restore_command(file, arguments).await,
+    };
+
+    match result {
+        Ok(()) => {
+            info!("command finished successfully");
+            0
+        }
+        Err(err) => {
+            error!(%err, "command failed due to error");
+            1
+        }
+    }
+}
+
+/// Opens the database of the stopped instance with root access.
+async fn open_root_database(
+    arguments: MycologArguments,
+) -> anyhow::Result<(DatabaseRootAccess, MycologConfig, MycologSecrets)> {
+    let config = try_parse_config(arguments)?;
+    prepare_application_dirs(&config)?;
+    let secrets = try_parse_secrets(&config)?;
+    let db = open_database_system(&config, &secrets).await?;
+    Ok((db.auth_root(), config, secrets))
+}
@@ -0,0 +1,31 @@
+use std::path::PathBuf;
+
+use anyhow::anyhow;
+use tokio::io::BufReader;
+use tracing::info;
+
+use crate::application::{restore_backup_archive, ImageManager};
+use crate::cli::MycologArguments;
+use crate::commands::open_root_database;
+
+pub async fn restore_command(file: PathBuf, arguments: MycologArguments) -> anyhow::Result<()> {
+    let (db, config, secrets) = open_root_database(arguments).await?;
+
+    let backup_file = tokio::fs::File::open(&file)
+        .await
+        .map_err(|err| anyhow!("unable to open {}: {:?}", file.display(), err))?;
+    restore_backup_archive(
+        &db,
+        &config.images_dir,
+        secrets.backup.key().as_ref(),
+        BufReader::new(backup_file),
+    )
+    .await?;
+    info!(file = %file.display(), "backup restored");
+
+    // Remove images which are not part of the restored backup
+    let images = ImageManager::new(&config.images_dir, db, config.images_max_bytes_per_user)?;
+    images.clean().await?;
+
+    Ok(())
+}
@@ -0,0 +1,14 @@
+use tracing::info;
+
+use crate::application::MigrationManager;
+use crate::cli::MycologArguments;
+use crate::commands::open_root_database;
+
+pub async fn rollback_command(count: u32, arguments: MycologArguments) -> anyhow::Result<()> {
+    let (db, config, _) = open_root_database(arguments).await?;
+    let manager = MigrationManager::new(&config.migrations_dir).await?;
+
+    let reverted = manager.rollback_migrations(&db, count).await?;
+    info!("rolled back {} migrations", reverted.len());
+    Ok(())
+}
@@ -0,0 +1,25 @@
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::OsRng;
+use tracing::info;
+
+use crate::secrets::{hash_admin_token, AdminScope};
+
+/// Prints a random token and its hashed entry, only the hash is stored in the secrets.
+pub fn generate_admin_token_command(name: String, scopes: Vec<AdminScope>) -> anyhow::Result<()> {
+    let mut token = [0u8; 32];
+    OsRng.fill_bytes(&mut token);
+    let token = hex::encode(token);
+    let scopes = scopes
+        .iter()
+        .map(|scope| format!("\"{}\"", scope.as_str()))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    info!(name, "generated admin token, it is not shown again");
+    println!("token: {token}");
+    println!();
+    println!("[tokens.{name}]");
+    println!("hash = \"{}\"", hex::encode(hash_admin_token(&token)));
+    println!("scopes = [{scopes}]");
+    Ok(())
+}
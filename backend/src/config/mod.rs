@@ -0,0 +1,1200 @@
+use std::fmt::Arguments;
+use std::fs::{write, File};
+use std::io::Read;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail};
+use email_address_parser::EmailAddress;
+use reqwest::Url;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use toml::{from_str, Table, Value};
+use tracing::{debug, error, info, instrument, warn};
+
+use crate::application::{
+    BackupLimit, BackupTargetConfig, BackupTargetKind, DatabaseEngine, MigrationDriftPolicy,
+};
+use crate::cli::MycologArguments;
+pub use crate::config::report::{ConfigProblem, ConfigReport};
+pub use crate::config::schema::config_json_schema;
+use crate::config::schema::validate_config_table;
+
+mod report;
+mod schema;
+
+pub fn parse_config(arguments: MycologArguments) -> MycologConfig {
+    match try_parse_config(arguments) {
+        Ok(config) => config,
+        Err(err) => {
+            match err.downcast_ref::<ConfigReport>() {
+                Some(report) => report.log(),
+                None => error!(%err, "configuration failed to load due to error"),
+            }
+            exit(2);
+        }
+    }
+}
+
+/// Reads and validates the layered config. All problems are collected and returned as a single
+/// [ConfigReport] error.
+#[instrument]
+pub fn try_parse_config(arguments: MycologArguments) -> anyhow::Result<MycologConfig> {
+    let mut report = ConfigReport::default();
+    let mut config_table = try_read_config(&arguments)?;
+    validate_config_table(&mut config_table, &mut report);
+    let config_file: ConfigFile = Value::Table(config_table).try_into()?;
+    let default_config = MycologConfig::default();
+
+    let mut should_write_config = false;
+
+    let web_file = match &config_file.web {
+        Some(file) => file.clone(),
+        None => Default::default(),
+    };
+    let web_bind_ip = if let Some(web_bind_ip) = &web_file.ip {
+        IpAddr::from_str(web_bind_ip).unwrap_or_else(|err| {
+            report.push(
+                "web.ip",
+                format!("`{web_bind_ip}` is no valid ip address: {err}"),
+            );
+            default_config.web_bind_ip
+        })
+    } else {
+        warn!("`web.ip` is missing from config");
+        should_write_config = true;
+        default_config.web_bind_ip
+    };
+    let web_bind_port = if let Some(web_bind_port) = &web_file.port {
+        *web_bind_port
+    } else {
+        warn!("`web.port` is missing from config");
+        should_write_config = true;
+        default_config.web_bind_port
+    };
+    let web_public_url = if let Some(web_public_url) = &web_file.public_url {
+        match Url::parse(web_public_url) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => {
+                web_public_url.trim_end_matches('/').to_string()
+            }
+            Ok(_) => {
+                report.push(
+                    "web.public_url",
+                    format!("`{web_public_url}` is no http or https url"),
+                );
+                default_config.web_public_url
+            }
+            Err(err) => {
+                report.push(
+                    "web.public_url",
+                    format!("`{web_public_url}` is no valid url: {err}"),
+                );
+                default_config.web_public_url
+            }
+        }
+    } else {
+        warn!("`web.public_url` is missing from config");
+        should_write_config = true;
+        default_config.web_public_url
+    };
+
+    let database_file = match &config_file.database {
+        Some(file) => file.clone(),
+        None => Default::default(),
+    };
+    let database_engine = match database_file.engine {
+        Some(EngineConfig::Memory) => DatabaseEngine::Memory,
+        Some(engine) => {
+            let path = if let Some(path) = &database_file.path {
+                PathBuf::from(path)
+            } else {
+                warn!("`database.path` is missing from config");
+                should_write_config = true;
+                PathBuf::from("data/")
+            };
+            // Only the memory and speedb engines are compiled into surrealdb
+            if let EngineConfig::Rocksdb | EngineConfig::File = engine {
+                report.push(
+                    "database.engine",
+                    "only the `memory` and `speedb` engines are supported by this build",
+                );
+            }
+            match engine {
+                EngineConfig::Memory => DatabaseEngine::Memory,
+                EngineConfig::Speedb => DatabaseEngine::Speedb { path },
+                EngineConfig::Rocksdb => DatabaseEngine::Rocksdb { path },
+                EngineConfig::File => DatabaseEngine::File { path },
+            }
+        }
+        None => {
+            warn!("`database.engine` is missing from config");
+            should_write_config = true;
+            default_config.database_engine
+        }
+    };
+
+    let paths_file = match &config_file.paths {
+        Some(file) => file.clone(),
+        None => Default::default(),
+    };
+    let mut path_or_default = |path: &Option<String>, key: &str, default: PathBuf| {
+        if let Some(path) = path {
+            PathBuf::from(path)
+        } else {
+            warn!("`paths.{key}` is missing from config");
+            should_write_config = true;
+            default
+        }
+    };
+    let data_dir = path_or_default(&paths_file.data, "data", default_config.data_dir);
+    let images_dir = path_or_default(&paths_file.images, "images", default_config.images_dir);
+    let backups_dir = path_or_default(&paths_file.backups, "backups", default_config.backups_dir);
+    let logs_dir = path_or_default(&paths_file.logs, "logs", default_config.logs_dir);
+    let migrations_dir = path_or_default(
+        &paths_file.migrations,
+        "migrations",
+        default_config.migrations_dir,
+    );
+    let schedules_dir = path_or_default(
+        &paths_file.schedules,
+        "schedules",
+        default_config.schedules_dir,
+    );
+    let emails_dir = path_or_default(&paths_file.emails, "emails", default_config.emails_dir);
+    let site_dir = path_or_default(&paths_file.site, "site", default_config.site_dir);
+
+    let email_file = match &config_file.email {
+        Some(file) => file.clone(),
+        None => Default::default(),
+    };
+    let email_noreply_sender = if let Some(email_noreply_sender) = &email_file.noreply_sender {
+        if !EmailAddress::is_valid(email_noreply_sender, None) {
+            report.push(
+                "email.noreply_sender",
+                format!("`{email_noreply_sender}` is no valid email address"),
+            );
+        }
+        email_noreply_sender.clone()
+    } else {
+        warn!("`email.noreply_sender` is is missing from config");
+        should_write_config = true;
+        default_config.email_noreply_sender
+    };
+    let email_admin_address = email_file.admin_address.clone();
+    if let Some(email_admin_address) = &email_admin_address
+        && !EmailAddress::is_valid(email_admin_address, None)
+    {
+        report.push(
+            "email.admin_address",
+            format!("`{email_admin_address}` is no valid email address"),
+        );
+    }
+
+    let schedules_file = match &config_file.schedules {
+        Some(file) => file.clone(),
+        None => Default::default(),
+    };
+    let schedule_alert_after_failures =
+        if let Some(alert_after_failures) = schedules_file.alert_after_failures {
+            alert_after_failures
+        } else {
+            warn!("`schedules.alert_after_failures` is missing from config");
+            should_write_config = true;
+            default_config.schedule_alert_after_failures
+        };
+
+    let images_file = match &config_file.images {
+        Some(file) => file.clone(),
+        None => Default::default(),
+    };
+    let images_max_bytes_per_user =
+        if let Some(images_max_bytes_per_user) = images_file.max_bytes_per_user {
+            images_max_bytes_per_user
+        } else {
+            warn!("`images.max_bytes_per_user` is is missing from config");
+            should_write_config = true;
+            default_config.images_max_bytes_per_user
+        };
+
+    let watch_file = match &config_file.watch {
+        Some(file) => file.clone(),
+        None => Default::default(),
+    };
+    let watch_enabled = if let Some(watch_enabled) = watch_file.enabled {
+        watch_enabled
+    } else {
+        warn!("`watch.enabled` is missing from config");
+        should_write_config = true;
+        default_config.watch_enabled
+    };
+    let watch_apply_migrations = if let Some(watch_apply_migrations) = watch_file.apply_migrations {
+        watch_apply_migrations
+    } else {
+        warn!("`watch.apply_migrations` is missing from config");
+        should_write_config = true;
+        default_config.watch_apply_migrations
+    };
+
+    let development_file = match &config_file.development {
+        Some(file) => file.clone(),
+        None => Default::default(),
+    };
+    let development = if let Some(development) = development_file.enabled {
+        development
+    } else {
+        warn!("`development.enabled` is missing from config");
+        should_write_config = true;
+        default_config.development
+    };
+
+    let migrations_file = match &config_file.migrations {
+        Some(file) => file.clone(),
+        None => Default::default(),
+    };
+    let migration_drift_policy = match migrations_file.on_drift {
+        Some(OnDriftConfig::Warn) => MigrationDriftPolicy::Warn,
+        Some(OnDriftConfig::Refuse) => MigrationDriftPolicy::Refuse,
+        None => {
+            warn!("`migrations.on_drift` is missing from config");
+            should_write_config = true;
+            default_config.migration_drift_policy
+        }
+    };
+
+    let queue_file = match &config_file.queue {
+        Some(file) => file.clone(),
+        None => Default::default(),
+    };
+    let queue_workers = if let Some(queue_workers) = queue_file.workers {
+        queue_workers
+    } else {
+        warn!("`queue.workers` is missing from config");
+        should_write_config = true;
+        default_config.queue_workers
+    };
+    let queue_max_attempts = if let Some(queue_max_attempts) = queue_file.max_attempts {
+        queue_max_attempts
+    } else {
+        warn!("`queue.max_attempts` is missing from config");
+        should_write_config = true;
+        default_config.queue_max_attempts
+    };
+    let queue_visibility_timeout_secs =
+        if let Some(visibility_timeout) = queue_file.visibility_timeout {
+            visibility_timeout
+        } else {
+            warn!("`queue.visibility_timeout` is missing from config");
+            should_write_config = true;
+            default_config.queue_visibility_timeout_secs
+        };
+
+    let backup_file = match &config_file.backups {
+        Some(file) => file.clone(),
+        None => Default::default(),
+    };
+    let backup_delay_hours = if let Some(backup_delay_hours) = &backup_file.delay_hours {
+        *backup_delay_hours
+    } else {
+        warn!("`backups.delay_hours` is is missing from config");
+        should_write_config = true;
+        default_config.backup_delay_hours
+    };
+
+    let backup_interval_hours = if let Some(backup_interval_hours) = &backup_file.interval_hours {
+        *backup_interval_hours
+    } else {
+        warn!("`backups.interval_hours` is is missing from config");
+        should_write_config = true;
+        default_config.backup_interval_hours
+    };
+
+    let backup_limit = BackupLimit::from(&backup_file.retention);
+    if backup_limit.is_unlimited() {
+        report.push("backups", "neither `max_age`, `max_size`, `max_amount` nor any of `keep_hourly`, `keep_daily`, `keep_weekly` or `keep_monthly` was found in config");
+    }
+    for (key, conflict) in backup_limit.conflicts() {
+        report.push(format!("backups.{key}"), conflict);
+    }
+
+    let mut backup_targets: Vec<BackupTargetConfig> = Vec::new();
+    for (index, target_file) in backup_file.targets.iter().flatten().enumerate() {
+        let Some(target) = parse_backup_target(target_file, index, &mut report) else {
+            continue;
+        };
+        if backup_targets
+            .iter()
+            .any(|existing| existing.name == target.name)
+        {
+            report.push(
+                format!("backups.targets[{index}].name"),
+                format!("backup target `{}` is defined more than once", target.name),
+            );
+            continue;
+        }
+        backup_targets.push(target);
+    }
+
+    if !report.is_empty() {
+        return Err(report.into());
+    }
+
+    let mut config = MycologConfig {
+        web_bind_ip,
+        web_bind_port,
+        web_public_url,
+        database_engine,
+        data_dir,
+        images_dir,
+        backups_dir,
+        logs_dir,
+        migrations_dir,
+        schedules_dir,
+        emails_dir,
+        site_dir,
+        email_noreply_sender,
+        email_admin_address,
+        images_max_bytes_per_user,
+        schedule_alert_after_failures,
+        watch_enabled,
+        watch_apply_migrations,
+        development,
+        migration_drift_policy,
+        queue_workers,
+        queue_max_attempts,
+        queue_visibility_timeout_secs,
+        backup_delay_hours,
+        backup_interval_hours,
+        backup_limit,
+        backup_targets,
+    };
+
+    if should_write_config {
+        if arguments.read_only_config {
+            info!("config is read-only, missing values are not written back");
+        } else if let Err(err) = try_write_config(&arguments) {
+            warn!(%err, "config parsing failed due to locked file");
+        }
+    }
+
+    if let Some(port) = arguments.port {
+        config.web_bind_port = port;
+    }
+    if let Some(ip) = arguments.hostname {
+        config.web_bind_ip = ip;
+    }
+
+    Ok(config)
+}
+
+/// Parses a backup target, problems are added to the report and result in [None].
+fn parse_backup_target(
+    target_file: &TargetConfig,
+    index: usize,
+    report: &mut ConfigReport,
+) -> Option<BackupTargetConfig> {
+    let key = format!("backups.targets[{index}]");
+    let Some(name) = target_file.name.clone() else {
+        report.push(format!("{key}.name"), "missing from config");
+        return None;
+    };
+    if name == "local" {
+        report.push(
+            format!("{key}.name"),
+            "`local` is reserved for the local backups directory",
+        );
+        return None;
+    }
+    let mut required = |value: &Option<String>, field: &str| {
+        if value.is_none() {
+            report.push(
+                format!("{key}.{field}"),
+                format!("missing from config for backup target `{name}`"),
+            );
+        }
+        value.clone().unwrap_or_default()
+    };
+
+    let kind = match target_file.kind {
+        Some(TargetKindConfig::Directory) => BackupTargetKind::Directory {
+            path: required(&target_file.path, "path").into(),
+        },
+        Some(TargetKindConfig::Rsync) => BackupTargetKind::Rsync {
+            host: required(&target_file.host, "host"),
+            path: required(&target_file.path, "path"),
+        },
+        Some(TargetKindConfig::S3) => BackupTargetKind::S3 {
+            endpoint: required(&target_file.endpoint, "endpoint"),
+            region: target_file
+                .region
+                .clone()
+                .unwrap_or("us-east-1".to_string()),
+            bucket: required(&target_file.bucket, "bucket"),
+            prefix: target_file.prefix.clone().unwrap_or_default(),
+        },
+        None => {
+            report.push(
+                format!("{key}.type"),
+                format!("missing from config for backup target `{name}`"),
+            );
+            return None;
+        }
+    };
+
+    let limit = BackupLimit::from(&target_file.retention);
+    if limit.is_unlimited() {
+        report.push(
+            key.clone(),
+            format!("backup target `{name}` has no retention limit configured"),
+        );
+    }
+    for (limit_key, conflict) in limit.conflicts() {
+        report.push(format!("{key}.{limit_key}"), conflict);
+    }
+
+    Some(BackupTargetConfig { name, kind, limit })
+}
+
+/// The configured log directory, read ahead of the remaining config so logging can be set up
+/// before the config is parsed. Falls back to `logs/` if the config is unreadable.
+pub fn read_logs_dir(arguments: &MycologArguments) -> PathBuf {
+    read_config_value(arguments, "paths", "logs")
+        .and_then(|logs_dir| logs_dir.as_str().map(PathBuf::from))
+        .unwrap_or(PathBuf::from("logs/"))
+}
+
+/// Whether this is a development setup, read ahead like [read_logs_dir].
+pub fn read_development(arguments: &MycologArguments) -> bool {
+    read_config_value(arguments, "development", "enabled")
+        .and_then(|enabled| enabled.as_bool())
+        .unwrap_or(false)
+}
+
+fn read_config_value(arguments: &MycologArguments, section: &str, key: &str) -> Option<Value> {
+    let mut config_table = try_read_config(arguments).ok()?;
+    config_table.remove(section)?.get(key).cloned()
+}
+
+/// The base config file, `config/config.toml` unless overridden via `--config`.
+fn base_config_path(arguments: &MycologArguments) -> PathBuf {
+    arguments
+        .config
+        .clone()
+        .unwrap_or(PathBuf::from("config/config.toml"))
+}
+
+/// The optional local override next to the base config file, e.g. `config/config.local.toml`.
+fn local_config_path(base_path: &Path) -> PathBuf {
+    base_path.with_extension("local.toml")
+}
+
+/// Reads the layered config. The local override file is merged on top of the base file and
+/// `MYCOLOG_` environment variables on top of both.
+fn try_read_config(arguments: &MycologArguments) -> anyhow::Result<Table> {
+    let base_path = base_config_path(arguments);
+    let mut config = read_config_table(&base_path)?;
+
+    let local_path = local_config_path(&base_path);
+    if local_path.is_file() {
+        debug!(file = %local_path.display(), "merging local config override");
+        merge_config_tables(&mut config, read_config_table(&local_path)?);
+    }
+    merge_config_tables(&mut config, env_config_table(std::env::vars()));
+
+    Ok(config)
+}
+
+fn read_config_table(path: &Path) -> anyhow::Result<Table> {
+    let mut config_file = File::open(path)
+        .map_err(|err| anyhow!("unable to open config file {}: {err}", path.display()))?;
+    let mut read_config_file = String::new();
+    config_file.read_to_string(&mut read_config_file)?;
+    from_str(&read_config_file)
+        .map_err(|err| anyhow!("config file {} is invalid: {err}", path.display()))
+}
+
+/// Builds a config table from environment variables like `MYCOLOG_WEB__PORT=8080`, where `__`
+/// separates the section from the key. Values are parsed as TOML and used as plain strings
+/// if that fails.
+fn env_config_table(vars: impl Iterator<Item = (String, String)>) -> Table {
+    let mut config = Table::new();
+    for (name, raw_value) in vars {
+        let Some(name) = name.strip_prefix("MYCOLOG_") else {
+            continue;
+        };
+        let keys = name
+            .split("__")
+            .map(|key| key.to_lowercase())
+            .collect::<Vec<_>>();
+        let Some((key, sections)) = keys.split_last() else {
+            continue;
+        };
+        if sections.is_empty() || keys.iter().any(|key| key.is_empty()) {
+            continue;
+        }
+
+        let value = from_str::<Table>(&format!("value = {raw_value}"))
+            .ok()
+            .and_then(|mut table| table.remove("value"))
+            .unwrap_or(Value::String(raw_value));
+        let mut table = &mut config;
+        for section in sections {
+            let entry = table
+                .entry(section.clone())
+                .or_insert(Value::Table(Table::new()));
+            if !entry.is_table() {
+                *entry = Value::Table(Table::new());
+            }
+            table = entry.as_table_mut().expect("entry was just made a table");
+        }
+        debug!(
+            key = keys.join("."),
+            "config value overridden by environment"
+        );
+        table.insert(key.clone(), value);
+    }
+    config
+}
+
+/// Recursively merges `overlay` into `base`, values of `overlay` take precedence.
+/// Arrays are replaced as a whole.
+fn merge_config_tables(base: &mut Table, overlay: Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base_table)), Value::Table(overlay_table)) => {
+                merge_config_tables(base_table, overlay_table)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Fills the keys missing from the base config file with their default values. Only the base
+/// file is considered, so values of the local override and environment are not persisted.
+fn try_write_config(arguments: &MycologArguments) -> anyhow::Result<()> {
+    let base_path = base_config_path(arguments);
+    let config = fill_missing_config(read_config_table(&base_path)?)?;
+    Ok(write(base_path, toml::to_string(&config)?)?)
+}
+
+/// Keys of the `backups` table which decide what backups are deleted.
+const RETENTION_KEYS: [&str; 7] = [
+    "max_amount",
+    "max_size",
+    "max_age",
+    "keep_hourly",
+    "keep_daily",
+    "keep_weekly",
+    "keep_monthly",
+];
+
+/// Merges the given config over the default config. Retention is never filled in, since a
+/// default limit would delete backups the user meant to keep, and an existing `backups` table is
+/// left untouched entirely.
+fn fill_missing_config(config: Table) -> anyhow::Result<Table> {
+    let default_config_file: ConfigFile = MycologConfig::default().into();
+    let Value::Table(mut defaults) = Value::try_from(default_config_file)? else {
+        bail!("default config is no table");
+    };
+    if config.contains_key("backups") {
+        defaults.remove("backups");
+    } else if let Some(Value::Table(backups)) = defaults.get_mut("backups") {
+        backups.retain(|key, _| !RETENTION_KEYS.contains(&key));
+    }
+    merge_config_tables(&mut defaults, config);
+    Ok(defaults)
+}
+
+impl Default for MycologConfig {
+    fn default() -> Self {
+        Self {
+            web_bind_ip: IpAddr::from([127, 0, 0, 1]),
+            web_bind_port: 8031,
+            web_public_url: "http://127.0.0.1:8031".to_string(),
+            database_engine: DatabaseEngine::Speedb {
+                path: PathBuf::from("data/"),
+            },
+            data_dir: PathBuf::from("data/"),
+            images_dir: PathBuf::from("images/"),
+            backups_dir: PathBuf::from("backups/"),
+            logs_dir: PathBuf::from("logs/"),
+            migrations_dir: PathBuf::from("migrations/"),
+            schedules_dir: PathBuf::from("schedules/"),
+            emails_dir: PathBuf::from("emails/"),
+            site_dir: PathBuf::from("site/"),
+            email_noreply_sender: "noreply@example.com".to_string(),
+            email_admin_address: None,
+            images_max_bytes_per_user: 2u64.pow(30), // 1GB,
+            schedule_alert_after_failures: 3,
+            watch_enabled: true,
+            watch_apply_migrations: false,
+            development: false,
+            migration_drift_policy: MigrationDriftPolicy::Warn,
+            queue_workers: 2,
+            queue_max_attempts: 5,
+            queue_visibility_timeout_secs: 300,
+            backup_delay_hours: 24,
+            backup_interval_hours: 24,
+            backup_limit: BackupLimit {
+                max_amount: Some(7),
+                ..Default::default()
+            },
+            backup_targets: Vec::new(),
+        }
+    }
+}
+
+impl From<MycologConfig> for ConfigFile {
+    fn from(value: MycologConfig) -> Self {
+        (&value).into()
+    }
+}
+
+impl From<&MycologConfig> for ConfigFile {
+    fn from(value: &MycologConfig) -> Self {
+        let (engine, path) = match &value.database_engine {
+            DatabaseEngine::Memory => (EngineConfig::Memory, None),
+            DatabaseEngine::Speedb { path } => (EngineConfig::Speedb, Some(path)),
+            DatabaseEngine::Rocksdb { path } => (EngineConfig::Rocksdb, Some(path)),
+            DatabaseEngine::File { path } => (EngineConfig::File, Some(path)),
+        };
+        let path_string = |path: &PathBuf| Some(path.display().to_string());
+        ConfigFile {
+            database: Some(DatabaseConfig {
+                engine: Some(engine),
+                path: path.and_then(path_string),
+            }),
+            paths: Some(PathsConfig {
+                data: path_string(&value.data_dir),
+                images: path_string(&value.images_dir),
+                backups: path_string(&value.backups_dir),
+                logs: path_string(&value.logs_dir),
+                migrations: path_string(&value.migrations_dir),
+                schedules: path_string(&value.schedules_dir),
+                emails: path_string(&value.emails_dir),
+                site: path_string(&value.site_dir),
+            }),
+            email: Some(EmailConfig {
+                noreply_sender: Some(value.email_noreply_sender.clone()),
+                admin_address: value.email_admin_address.clone(),
+            }),
+            images: Some(ImagesConfig {
+                max_bytes_per_user: Some(value.images_max_bytes_per_user),
+            }),
+            schedules: Some(SchedulesConfig {
+                alert_after_failures: Some(value.schedule_alert_after_failures),
+            }),
+            web: Some(WebConfig {
+                ip: Some(value.web_bind_ip.to_string()),
+                port: Some(value.web_bind_port),
+                public_url: Some(value.web_public_url.clone()),
+            }),
+            watch: Some(WatchConfig {
+                enabled: Some(value.watch_enabled),
+                apply_migrations: Some(value.watch_apply_migrations),
+            }),
+            development: Some(DevelopmentConfig {
+                enabled: Some(value.development),
+            }),
+            migrations: Some(MigrationsConfig {
+                on_drift: Some(match value.migration_drift_policy {
+                    MigrationDriftPolicy::Warn => OnDriftConfig::Warn,
+                    MigrationDriftPolicy::Refuse => OnDriftConfig::Refuse,
+                }),
+            }),
+            queue: Some(QueueConfig {
+                workers: Some(value.queue_workers),
+                max_attempts: Some(value.queue_max_attempts),
+                visibility_timeout: Some(value.queue_visibility_timeout_secs),
+            }),
+            backups: Some(BackupConfig {
+                delay_hours: Some(value.backup_delay_hours),
+                interval_hours: Some(value.backup_interval_hours),
+                retention: (&value.backup_limit).into(),
+                targets: (!value.backup_targets.is_empty())
+                    .then(|| value.backup_targets.iter().map(Into::into).collect()),
+            }),
+        }
+    }
+}
+
+impl From<&BackupTargetConfig> for TargetConfig {
+    fn from(value: &BackupTargetConfig) -> Self {
+        let target = TargetConfig {
+            name: Some(value.name.clone()),
+            retention: (&value.limit).into(),
+            ..Default::default()
+        };
+        match &value.kind {
+            BackupTargetKind::Directory { path } => TargetConfig {
+                kind: Some(TargetKindConfig::Directory),
+                path: Some(path.display().to_string()),
+                ..target
+            },
+            BackupTargetKind::Rsync { host, path } => TargetConfig {
+                kind: Some(TargetKindConfig::Rsync),
+                host: Some(host.clone()),
+                path: Some(path.clone()),
+                ..target
+            },
+            BackupTargetKind::S3 {
+                endpoint,
+                region,
+                bucket,
+                prefix,
+            } => TargetConfig {
+                kind: Some(TargetKindConfig::S3),
+                endpoint: Some(endpoint.clone()),
+                region: Some(region.clone()),
+                bucket: Some(bucket.clone()),
+                prefix: Some(prefix.clone()),
+                ..target
+            },
+        }
+    }
+}
+
+impl From<&RetentionConfig> for BackupLimit {
+    fn from(value: &RetentionConfig) -> Self {
+        BackupLimit {
+            max_amount: value.max_amount,
+            max_size_mb: value.max_size,
+            max_age_hours: value.max_age,
+            keep_hourly: value.keep_hourly,
+            keep_daily: value.keep_daily,
+            keep_weekly: value.keep_weekly,
+            keep_monthly: value.keep_monthly,
+        }
+    }
+}
+
+impl From<&BackupLimit> for RetentionConfig {
+    fn from(value: &BackupLimit) -> Self {
+        RetentionConfig {
+            max_amount: value.max_amount,
+            max_size: value.max_size_mb,
+            max_age: value.max_age_hours,
+            keep_hourly: value.keep_hourly,
+            keep_daily: value.keep_daily,
+            keep_weekly: value.keep_weekly,
+            keep_monthly: value.keep_monthly,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MycologConfig {
+    // Web
+    pub web_bind_ip: IpAddr,
+    pub web_bind_port: u16,
+    /// Address the site is reachable at from the outside, used for links in emails.
+    pub web_public_url: String,
+
+    // Database
+    pub database_engine: DatabaseEngine,
+
+    // Paths
+    pub data_dir: PathBuf,
+    pub images_dir: PathBuf,
+    pub backups_dir: PathBuf,
+    pub logs_dir: PathBuf,
+    pub migrations_dir: PathBuf,
+    pub schedules_dir: PathBuf,
+    pub emails_dir: PathBuf,
+    pub site_dir: PathBuf,
+
+    // Email
+    pub email_noreply_sender: String,
+    pub email_admin_address: Option<String>,
+
+    // Images
+    pub images_max_bytes_per_user: u64,
+
+    // Schedules
+    pub schedule_alert_after_failures: u32,
+
+    // File watching
+    pub watch_enabled: bool,
+    pub watch_apply_migrations: bool,
+
+    // Development
+    /// Development setups log verbosely, allow cross-origin requests and issue auth cookies
+    /// for any site.
+    pub development: bool,
+
+    // Migrations
+    pub migration_drift_policy: MigrationDriftPolicy,
+
+    // Job queue
+    pub queue_workers: u32,
+    pub queue_max_attempts: u32,
+    pub queue_visibility_timeout_secs: u64,
+
+    // Backups
+    pub backup_delay_hours: u64,
+    pub backup_interval_hours: u64,
+    pub backup_limit: BackupLimit,
+    pub backup_targets: Vec<BackupTargetConfig>,
+}
+
+/// Layout of the config file. The schema derived from it validates the config before it is
+/// deserialized, see [schema].
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+struct ConfigFile {
+    /// Storage engine of the database.
+    database: Option<DatabaseConfig>,
+    /// Data directories, relative to the working directory.
+    paths: Option<PathsConfig>,
+    /// Email settings.
+    email: Option<EmailConfig>,
+    /// Image storage settings.
+    images: Option<ImagesConfig>,
+    /// Scheduled job settings.
+    schedules: Option<SchedulesConfig>,
+    /// Web server settings.
+    web: Option<WebConfig>,
+    /// File watching settings.
+    watch: Option<WatchConfig>,
+    /// Development setup settings.
+    development: Option<DevelopmentConfig>,
+    /// Database migration settings.
+    migrations: Option<MigrationsConfig>,
+    /// Job queue settings.
+    queue: Option<QueueConfig>,
+    /// Backup settings and retention of local backups.
+    backups: Option<BackupConfig>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+struct DatabaseConfig {
+    /// Storage engine, `memory` does not persist anything.
+    engine: Option<EngineConfig>,
+    /// Directory of the datastore, unused by the `memory` engine.
+    path: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+enum EngineConfig {
+    Memory,
+    Speedb,
+    Rocksdb,
+    File,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+struct PathsConfig {
+    /// Directory of the database files.
+    data: Option<String>,
+    /// Directory of uploaded images.
+    images: Option<String>,
+    /// Directory of local backups.
+    backups: Option<String>,
+    /// Directory of log files.
+    logs: Option<String>,
+    /// Directory of migration files.
+    migrations: Option<String>,
+    /// Directory of schedule files.
+    schedules: Option<String>,
+    /// Directory of email templates.
+    emails: Option<String>,
+    /// Directory of the static website.
+    site: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+struct EmailConfig {
+    /// Sender address of all emails.
+    noreply_sender: Option<String>,
+    /// Address receiving administrative alerts.
+    admin_address: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+struct ImagesConfig {
+    /// Maximum size of all images of a single user in bytes.
+    #[schemars(range(min = 1))]
+    max_bytes_per_user: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+struct SchedulesConfig {
+    /// Consecutive failures of a schedule after which the admin is alerted.
+    #[schemars(range(min = 1))]
+    alert_after_failures: Option<u32>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+struct WebConfig {
+    /// IP address to bind the server to.
+    ip: Option<String>,
+    /// Port to listen on.
+    #[schemars(range(min = 1, max = 65535))]
+    port: Option<u16>,
+    /// Address the site is reachable at, used for links in emails.
+    public_url: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+struct DevelopmentConfig {
+    /// Log verbosely to stdout only, allow cross-origin requests and issue auth cookies for any
+    /// site, e.g. for a separately served frontend.
+    enabled: Option<bool>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+struct WatchConfig {
+    /// Reload schedules, emails and migrations when they change on disk.
+    enabled: Option<bool>,
+    /// Apply changed migration files while running.
+    apply_migrations: Option<bool>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+struct MigrationsConfig {
+    /// Reaction to applied migrations being modified or removed.
+    on_drift: Option<OnDriftConfig>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+enum OnDriftConfig {
+    Warn,
+    Refuse,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+struct QueueConfig {
+    /// Amount of concurrent job workers.
+    #[schemars(range(min = 1))]
+    workers: Option<u32>,
+    /// Attempts after which a failing job is given up.
+    #[schemars(range(min = 1))]
+    max_attempts: Option<u32>,
+    /// Seconds after which a running job is considered abandoned and executed again.
+    #[schemars(range(min = 1))]
+    visibility_timeout: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+struct BackupConfig {
+    /// Hours after startup until the first backup.
+    delay_hours: Option<u64>,
+    /// Hours between backups.
+    #[schemars(range(min = 1))]
+    interval_hours: Option<u64>,
+    #[serde(flatten)]
+    retention: RetentionConfig,
+    /// Off-site targets backups are copied to.
+    targets: Option<Vec<TargetConfig>>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+struct TargetConfig {
+    /// Unique name of the backup target.
+    name: Option<String>,
+    /// Kind of the backup target.
+    #[serde(rename = "type")]
+    kind: Option<TargetKindConfig>,
+    /// Directory of `directory` and `rsync` targets.
+    path: Option<String>,
+    /// Remote host of `rsync` targets.
+    host: Option<String>,
+    /// Endpoint url of `s3` targets.
+    endpoint: Option<String>,
+    /// Region of `s3` targets.
+    region: Option<String>,
+    /// Bucket of `s3` targets.
+    bucket: Option<String>,
+    /// Key prefix of `s3` targets.
+    prefix: Option<String>,
+    #[serde(flatten)]
+    retention: RetentionConfig,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+enum TargetKindConfig {
+    Directory,
+    Rsync,
+    S3,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+struct RetentionConfig {
+    /// Maximum amount of backups.
+    #[schemars(range(min = 1))]
+    max_amount: Option<u64>,
+    /// Maximum size of all backups in megabytes.
+    #[schemars(range(min = 1))]
+    max_size: Option<u64>,
+    /// Maximum age of backups in hours.
+    #[schemars(range(min = 1))]
+    max_age: Option<u64>,
+    /// Keep the newest backup of this many most recent hours.
+    #[schemars(range(min = 1))]
+    keep_hourly: Option<u64>,
+    /// Keep the newest backup of this many most recent days.
+    #[schemars(range(min = 1))]
+    keep_daily: Option<u64>,
+    /// Keep the newest backup of this many most recent weeks.
+    #[schemars(range(min = 1))]
+    keep_weekly: Option<u64>,
+    /// Keep the newest backup of this many most recent months.
+    #[schemars(range(min = 1))]
+    keep_monthly: Option<u64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(vars: &[(&str, &str)]) -> Table {
+        env_config_table(
+            vars.iter()
+                .map(|(name, value)| (name.to_string(), value.to_string())),
+        )
+    }
+
+    #[test]
+    fn env_values_are_parsed_as_toml() {
+        let table = env(&[
+            ("MYCOLOG_WEB__PORT", "8080"),
+            ("MYCOLOG_WEB__IP", "0.0.0.0"),
+            ("MYCOLOG_WATCH__ENABLED", "true"),
+            ("MYCOLOG_EMAIL__ADMIN_ADDRESS", "\"admin@example.com\""),
+        ]);
+        assert_eq!(
+            table,
+            from_str::<Table>(
+                r#"
+                web = { port = 8080, ip = "0.0.0.0" }
+                watch = { enabled = true }
+                email = { admin_address = "admin@example.com" }
+                "#
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn env_ignores_foreign_and_malformed_names() {
+        let table = env(&[
+            ("PATH", "/usr/bin"),
+            ("MYCOLOG_PORT", "8080"),
+            ("MYCOLOG_WEB__", "8080"),
+            ("MYCOLOG___PORT", "8080"),
+            ("mycolog_web__port", "8080"),
+        ]);
+        assert!(table.is_empty(), "{table:?}");
+    }
+
+    #[test]
+    fn env_supports_nested_sections() {
+        let table = env(&[
+            ("MYCOLOG_BACKUPS", "1"),
+            ("MYCOLOG_BACKUPS__LIMITS__MAX_AMOUNT", "3"),
+        ]);
+        assert_eq!(
+            table["backups"]["limits"]["max_amount"].as_integer(),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn merge_overrides_values_and_keeps_sections() {
+        let mut base = from_str::<Table>(
+            r#"
+            [web]
+            ip = "127.0.0.1"
+            port = 8031
+
+            [database]
+            engine = "memory"
+            "#,
+        )
+        .unwrap();
+        let overlay = from_str::<Table>(
+            r#"
+            database = "replaced"
+
+            [web]
+            port = 8080
+
+            [watch]
+            enabled = true
+            "#,
+        )
+        .unwrap();
+        merge_config_tables(&mut base, overlay);
+        assert_eq!(
+            base,
+            from_str::<Table>(
+                r#"
+                database = "replaced"
+
+                [web]
+                ip = "127.0.0.1"
+                port = 8080
+
+                [watch]
+                enabled = true
+                "#
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn fill_keeps_existing_backups_table() {
+        let config = fill_missing_config(
+            from_str::<Table>(
+                r#"
+                [backups]
+                max_size = 1024
+                keep_daily = 14
+                "#,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            config["backups"].as_table().unwrap(),
+            &from_str::<Table>(
+                r#"
+                max_size = 1024
+                keep_daily = 14
+                "#
+            )
+            .unwrap()
+        );
+        assert!(config.contains_key("web"), "{config:?}");
+    }
+
+    #[test]
+    fn fill_never_adds_retention() {
+        let config = fill_missing_config(Table::new()).unwrap();
+        let backups = config["backups"].as_table().unwrap();
+        assert!(backups.contains_key("delay_hours"), "{backups:?}");
+        for key in RETENTION_KEYS {
+            assert!(!backups.contains_key(key), "{key} was filled in");
+        }
+    }
+}
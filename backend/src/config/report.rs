@@ -0,0 +1,53 @@
+use std::fmt::{Display, Formatter};
+
+use serde::Serialize;
+use tracing::error;
+
+/// Every problem found while validating the config, so all of them can be fixed at once.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ConfigReport {
+    pub problems: Vec<ConfigProblem>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ConfigProblem {
+    /// Dotted path of the offending key, e.g. `web.ip`.
+    pub key: String,
+    pub message: String,
+}
+
+impl ConfigReport {
+    pub fn push(&mut self, key: impl Into<String>, message: impl Into<String>) {
+        self.problems.push(ConfigProblem {
+            key: key.into(),
+            message: message.into(),
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    /// Logs every problem as a separate error.
+    pub fn log(&self) {
+        for problem in &self.problems {
+            error!(
+                key = problem.key,
+                problem = problem.message,
+                "invalid config"
+            );
+        }
+    }
+}
+
+impl Display for ConfigReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "found {} problems in config", self.problems.len())?;
+        for problem in &self.problems {
+            write!(f, "\n  `{}`: {}", problem.key, problem.message)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigReport {}
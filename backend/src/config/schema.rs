@@ -0,0 +1,253 @@
+use schemars::gen::SchemaSettings;
+use serde_json::json;
+use toml::{Table, Value};
+
+use crate::config::report::ConfigReport;
+use crate::config::ConfigFile;
+
+/// JSON schema of the config file, e.g. for completion in editors. It is derived from
+/// [ConfigFile], so it always matches what the server deserializes.
+pub fn config_json_schema() -> serde_json::Value {
+    let root = SchemaSettings::draft07()
+        .with(|settings| {
+            settings.option_add_null_type = false;
+            settings.inline_subschemas = true;
+        })
+        .into_generator()
+        .into_root_schema_for::<ConfigFile>();
+    let mut schema = serde_json::to_value(root).unwrap_or_else(|_| json!({}));
+    schema["title"] = json!("Mycolog configuration");
+    if let Some(schema) = schema.as_object_mut() {
+        schema.remove("description");
+    }
+    schema
+}
+
+/// Removes and reports every value of the config table which is unknown or does not match
+/// the schema, so the remaining table can be deserialized.
+pub fn validate_config_table(table: &mut Table, report: &mut ConfigReport) {
+    validate_table(table, &config_json_schema(), "", report);
+}
+
+fn validate_table(
+    table: &mut Table,
+    schema: &serde_json::Value,
+    prefix: &str,
+    report: &mut ConfigReport,
+) {
+    let keys = table.keys().cloned().collect::<Vec<_>>();
+    for key in keys {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}.{key}")
+        };
+        let Some(field) = schema["properties"].get(&key) else {
+            if schema["additionalProperties"] == json!(false) {
+                report.push(path, "unknown key");
+                table.remove(&key);
+            }
+            continue;
+        };
+        let Some(value) = table.get_mut(&key) else {
+            continue;
+        };
+        if let Err(message) = validate_value(value, field, &path, report) {
+            report.push(path, message);
+            table.remove(&key);
+        }
+    }
+}
+
+fn validate_value(
+    value: &mut Value,
+    schema: &serde_json::Value,
+    path: &str,
+    report: &mut ConfigReport,
+) -> Result<(), String> {
+    match schema["type"].as_str() {
+        Some("string") => {
+            let Some(string) = value.as_str() else {
+                return Err("expected a string".to_string());
+            };
+            if let Some(variants) = schema["enum"].as_array()
+                && !variants.iter().any(|variant| variant == string)
+            {
+                let variants = variants
+                    .iter()
+                    .filter_map(|variant| variant.as_str())
+                    .collect::<Vec<_>>();
+                return Err(format!("expected one of `{}`", variants.join("`, `")));
+            }
+            Ok(())
+        }
+        Some("boolean") if value.is_bool() => Ok(()),
+        Some("boolean") => Err("expected a boolean".to_string()),
+        Some("integer") => {
+            let Some(integer) = value.as_integer() else {
+                return Err("expected an integer".to_string());
+            };
+            if let Some(minimum) = schema["minimum"].as_f64()
+                && (integer as f64) < minimum
+            {
+                return Err(format!("must be at least {minimum}"));
+            }
+            if let Some(maximum) = schema["maximum"].as_f64()
+                && (integer as f64) > maximum
+            {
+                return Err(format!("must be at most {maximum}"));
+            }
+            Ok(())
+        }
+        Some("object") => {
+            let Some(table) = value.as_table_mut() else {
+                return Err("expected a table".to_string());
+            };
+            validate_table(table, schema, path, report);
+            Ok(())
+        }
+        Some("array") => {
+            let Some(array) = value.as_array_mut() else {
+                return Err("expected an array of tables".to_string());
+            };
+            for (index, item) in array.iter_mut().enumerate() {
+                let Some(table) = item.as_table_mut() else {
+                    return Err("expected an array of tables".to_string());
+                };
+                validate_table(table, &schema["items"], &format!("{path}[{index}]"), report);
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validate(config: &str) -> (Table, Vec<(String, String)>) {
+        let mut table = config.parse::<Table>().unwrap();
+        let mut report = ConfigReport::default();
+        validate_config_table(&mut table, &mut report);
+        let problems = report
+            .problems
+            .into_iter()
+            .map(|problem| (problem.key, problem.message))
+            .collect();
+        (table, problems)
+    }
+
+    #[test]
+    fn accepts_valid_config() {
+        let (_, problems) = validate(
+            r#"
+            [web]
+            ip = "0.0.0.0"
+            port = 8080
+
+            [database]
+            engine = "rocksdb"
+
+            [backups]
+            interval_hours = 24
+            keep_daily = 7
+
+            [[backups.targets]]
+            name = "nas"
+            type = "rsync"
+            keep_weekly = 4
+            "#,
+        );
+        assert!(problems.is_empty(), "{problems:?}");
+    }
+
+    #[test]
+    fn removes_unknown_keys() {
+        let (table, problems) = validate(
+            r#"
+            colour = "blue"
+
+            [web]
+            prot = 8080
+            "#,
+        );
+        assert_eq!(
+            problems,
+            vec![
+                ("colour".to_string(), "unknown key".to_string()),
+                ("web.prot".to_string(), "unknown key".to_string()),
+            ]
+        );
+        assert!(!table.contains_key("colour"));
+        assert!(table["web"].as_table().unwrap().is_empty());
+    }
+
+    #[test]
+    fn reports_invalid_values() {
+        let (table, problems) = validate(
+            r#"
+            [web]
+            port = 70000
+
+            [database]
+            engine = "sqlite"
+
+            [queue]
+            workers = 0
+
+            [watch]
+            enabled = "yes"
+
+            [[backups.targets]]
+            type = "s3"
+            max_size = -1
+            "#,
+        );
+        assert_eq!(
+            problems,
+            vec![
+                (
+                    "backups.targets[0].max_size".to_string(),
+                    "must be at least 1".to_string()
+                ),
+                (
+                    "database.engine".to_string(),
+                    "expected one of `memory`, `speedb`, `rocksdb`, `file`".to_string()
+                ),
+                (
+                    "queue.workers".to_string(),
+                    "must be at least 1".to_string()
+                ),
+                (
+                    "watch.enabled".to_string(),
+                    "expected a boolean".to_string()
+                ),
+                ("web.port".to_string(), "must be at most 65535".to_string()),
+            ]
+        );
+        let target = &table["backups"]["targets"][0];
+        assert_eq!(target.get("type").and_then(Value::as_str), Some("s3"));
+        assert!(target.get("max_size").is_none());
+    }
+
+    #[test]
+    fn reports_wrong_structure() {
+        let (_, problems) = validate(
+            r#"
+            web = 1
+            backups = { targets = [1] }
+            "#,
+        );
+        assert_eq!(
+            problems,
+            vec![
+                (
+                    "backups.targets".to_string(),
+                    "expected an array of tables".to_string()
+                ),
+                ("web".to_string(), "expected a table".to_string()),
+            ]
+        );
+    }
+}
@@ -1,9 +1,14 @@
+use std::collections::HashMap;
+use std::time::Instant;
+
 use tokio::sync::mpsc::Receiver;
-use tokio::sync::Mutex;
+use tokio::sync::{watch, Mutex};
 use tokio_util::sync::CancellationToken;
 use tokio_util::task::TaskTracker;
 
-use crate::application::{DatabaseSystem, EmailManager, ImageManager, ScheduleQueries};
+use crate::application::{
+    DatabaseSystem, EmailManager, ImageManager, JobQueue, JobRegistry, ScheduleQueries,
+};
 use crate::config::MycologConfig;
 use crate::secrets::MycologSecrets;
 use crate::shutdown::exit::ExitMessage;
@@ -16,9 +21,14 @@ pub struct MycologContext {
     pub db: DatabaseSystem,
     pub email: EmailManager,
     pub images: ImageManager,
-    pub schedules: ScheduleQueries,
+    /// Replaced whenever the schedule files change on disk.
+    pub schedules: watch::Sender<ScheduleQueries>,
+    pub jobs: JobRegistry,
+    pub queue: JobQueue,
 
     pub logging: LoggingHandle,
+    /// When the use of each admin token was last recorded in the database.
+    pub admin_token_uses: std::sync::Mutex<HashMap<String, Instant>>,
 
     pub tasks: TaskTracker,
 
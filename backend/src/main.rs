@@ -6,12 +6,14 @@
 use std::process::exit;
 
 use crate::application::run_application;
-use crate::cli::parse_arguments;
+use crate::cli::{parse_arguments, MycologCommand};
+use crate::commands::run_command;
 use crate::shutdown::shutdown;
 use crate::startup::startup;
 
 mod application;
 mod cli;
+mod commands;
 mod config;
 mod context;
 mod secrets;
@@ -22,6 +24,20 @@ mod utils;
 #[tokio::main]
 async fn main() {
     let arguments = parse_arguments();
+    if let Some(workdir) = &arguments.workdir
+        && let Err(err) = std::env::set_current_dir(workdir)
+    {
+        eprintln!(
+            "unable to change into working directory {}: {err}",
+            workdir.display()
+        );
+        exit(2);
+    }
+
+    match arguments.command() {
+        MycologCommand::Serve => {}
+        MycologCommand::Maintenance(command) => exit(run_command(command, arguments).await),
+    }
 
     let context = startup(arguments).await;
     let application_code = run_application(&context).await;
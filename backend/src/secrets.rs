@@ -1,18 +1,26 @@
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::{Debug, Formatter};
 use std::fs::File;
-use std::io::Read;
+use std::io::{ErrorKind, Read};
+use std::path::PathBuf;
 use std::process::exit;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
 
 use anyhow::{anyhow, bail};
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
 use toml::from_str;
-use tracing::{error, instrument};
+use tracing::{debug, error, instrument, warn};
 
-use crate::config::{try_parse_config, MycologConfig};
+use crate::config::MycologConfig;
 
-#[instrument]
-pub fn parse_secrets() -> MycologSecrets {
-    match try_parse_secrets() {
+#[instrument(skip_all)]
+pub fn parse_secrets(config: &MycologConfig) -> MycologSecrets {
+    match try_parse_secrets(config) {
         Ok(secrets) => secrets,
         Err(err) => {
             error!(err = err.to_string(), "secrets unable to read due to error");
@@ -21,69 +29,224 @@ pub fn parse_secrets() -> MycologSecrets {
     }
 }
 
-pub fn try_parse_secrets() -> anyhow::Result<MycologSecrets> {
-    let mut keys_file = try_read_secrets_keys()?;
-    let db_file = try_read_secrets_db()?;
-    let admin_file = try_read_secrets_admin()?;
+/// Resolves all secrets. Every secret is looked up in the `MYCOLOG_SECRET_<NAME>` environment
+/// variable, the credential file `<name>` inside `$CREDENTIALS_DIRECTORY` (as provided by
+/// systemd or docker) and the TOML files under `secrets/`, in that order.
+/// Only the database credentials are required, missing optional secrets disable the subsystem
+/// depending on them.
+pub fn try_parse_secrets(config: &MycologConfig) -> anyhow::Result<MycologSecrets> {
+    let keys_file: SecretsKeysFile = try_read_secrets_file("keys")?;
+    let db_file: SecretsDbFile = try_read_secrets_file("db")?;
+    let admin_file: SecretsAdminFile = try_read_secrets_file("admin")?;
+    let backup_file: SecretsBackupFile = try_read_secrets_file("backup")?;
 
-    let Some(mailersend_file) = keys_file.mailersend else {
-        bail!("no section for `mailersend` in secrets/keys.toml");
-    };
-    let mailersend_api = mailersend_file.api_key.ok_or(anyhow!(
-        "value for `mailersend.api_key` in secrets/keys.toml is missing"
-    ))?;
-    let mailersend_webhook = mailersend_file.webhook_signature.ok_or(anyhow!(
-        "value for `mailersend.webhook_signature` in secrets/keys.toml is missing"
-    ))?;
+    let mailersend_file = keys_file.mailersend.unwrap_or_default();
+    let mailersend_api = resolve_secret("mailersend_api_key", mailersend_file.api_key)?;
+    if mailersend_api.is_none() {
+        warn!("no mailersend api key configured, sending emails is disabled");
+    }
+    let mailersend_webhook = resolve_secret(
+        "mailersend_webhook_signature",
+        mailersend_file.webhook_signature,
+    )?;
+    if mailersend_webhook.is_none() {
+        warn!("no mailersend webhook signature configured, email webhook is disabled");
+    }
 
-    let db_user = db_file
-        .user
-        .ok_or(anyhow!("value for `user` in secrets/db.toml is missing"))?;
-    let db_password = db_file.password.ok_or(anyhow!(
-        "password for `password` in secrets/db.toml is missing"
-    ))?;
+    let db_user = resolve_secret("db_user", db_file.user)?
+        .ok_or(missing_secret_error("db_user", "user", "db"))?;
+    let db_password = resolve_secret("db_password", db_file.password)?
+        .ok_or(missing_secret_error("db_password", "password", "db"))?;
 
-    let admin_token = admin_file
-        .token
-        .ok_or(anyhow!(
-            "value for `token` in secrets/admin.toml is missing"
-        ))?
-        .chars()
-        .filter(|c| c.is_ascii())
-        .collect::<String>();
+    let mut admin_tokens = Vec::new();
+    if let Some(token) = resolve_secret("admin_token", admin_file.token)? {
+        warn!("single `token` in secrets/admin.toml is deprecated, use named `tokens` instead");
+        admin_tokens.push(AdminToken {
+            name: "default".to_string(),
+            hash: hash_admin_token(&token),
+            scopes: AdminScope::ALL.into_iter().collect(),
+            expires: None,
+        });
+    }
+    for (name, token_file) in admin_file.tokens.unwrap_or_default() {
+        admin_tokens.push(parse_admin_token(name, token_file)?);
+    }
+    if admin_tokens.is_empty() {
+        warn!("no admin token configured, admin api is disabled");
+    }
+
+    let backup_key = match resolve_secret("backup_key", backup_file.key)? {
+        Some(key) => {
+            let key = hex::decode(key.trim())
+                .map_err(|err| anyhow!("secret `backup_key` is not valid hex: {err}"))?;
+            let key: [u8; 32] = key
+                .try_into()
+                .map_err(|_| anyhow!("secret `backup_key` has to be exactly 32 bytes long"))?;
+            Some(key)
+        }
+        None => None,
+    };
+    let mut target_files = backup_file.targets.unwrap_or_default();
+    let target_names = target_files
+        .keys()
+        .cloned()
+        .chain(
+            config
+                .backup_targets
+                .iter()
+                .map(|target| target.name.clone()),
+        )
+        .collect::<BTreeSet<_>>();
+    let mut backup_targets = BTreeMap::new();
+    for name in target_names {
+        let target_file = target_files.remove(&name).unwrap_or_default();
+        let access_key_name = format!("backup_target_{name}_access_key");
+        let secret_key_name = format!("backup_target_{name}_secret_key");
+        let access_key = resolve_secret(&access_key_name, target_file.access_key)?;
+        let secret_key = resolve_secret(&secret_key_name, target_file.secret_key)?;
+        match (access_key, secret_key) {
+            (Some(access_key), Some(secret_key)) => {
+                backup_targets.insert(
+                    name,
+                    BackupTargetCredentials {
+                        access_key,
+                        secret_key,
+                    },
+                );
+            }
+            (None, None) => {}
+            (None, Some(_)) => {
+                return Err(missing_secret_error(
+                    &access_key_name,
+                    &format!("targets.{name}.access_key"),
+                    "backup",
+                ))
+            }
+            (Some(_), None) => {
+                return Err(missing_secret_error(
+                    &secret_key_name,
+                    &format!("targets.{name}.secret_key"),
+                    "backup",
+                ))
+            }
+        }
+    }
 
     Ok(MycologSecrets {
         keys: SecretsKeys {
             mailersend_api,
-            mailersend_webhook,
+            mailersend_webhook: Arc::new(RwLock::new(mailersend_webhook)),
         },
         db: SecretsDb {
             user: db_user,
             password: db_password,
         },
-        admin: SecretsAdmin { token: admin_token },
+        admin: SecretsAdmin {
+            tokens: Arc::new(RwLock::new(admin_tokens)),
+        },
+        backup: SecretsBackup {
+            key: backup_key,
+            targets: backup_targets,
+        },
+    })
+}
+
+/// Looks up the secret with the given name in the environment and the credentials directory,
+/// falling back to the value of the secrets file.
+fn resolve_secret(name: &str, file_value: Option<String>) -> anyhow::Result<Option<String>> {
+    if let Ok(value) = std::env::var(secret_env_name(name)) {
+        debug!(secret = name, "read secret from environment");
+        return Ok(Some(value));
+    }
+
+    if let Some(credentials_dir) = std::env::var_os("CREDENTIALS_DIRECTORY") {
+        let credential_path = PathBuf::from(credentials_dir).join(name);
+        match std::fs::read_to_string(&credential_path) {
+            Ok(value) => {
+                debug!(secret = name, "read secret from credentials directory");
+                return Ok(Some(value.trim_end_matches(['\r', '\n']).to_string()));
+            }
+            Err(err) if err.kind() == ErrorKind::NotFound => {}
+            Err(err) => bail!(
+                "unable to read credential file {}: {err}",
+                credential_path.display()
+            ),
+        }
+    }
+
+    Ok(file_value)
+}
+
+fn parse_admin_token(name: String, token_file: AdminTokenFile) -> anyhow::Result<AdminToken> {
+    let hash_name = format!("admin_token_{name}_hash");
+    let hash = resolve_secret(&hash_name, token_file.hash)?.ok_or(missing_secret_error(
+        &hash_name,
+        &format!("tokens.{name}.hash"),
+        "admin",
+    ))?;
+    let hash: [u8; 32] = hex::decode(hash.trim())
+        .ok()
+        .and_then(|hash| hash.try_into().ok())
+        .ok_or(anyhow!(
+            "value for `tokens.{name}.hash` in secrets/admin.toml has to be a hex encoded sha256 hash"
+        ))?;
+    let scopes = token_file
+        .scopes
+        .unwrap_or_default()
+        .iter()
+        .map(|scope| AdminScope::from_str(scope))
+        .collect::<anyhow::Result<BTreeSet<_>>>()
+        .map_err(|err| anyhow!("admin token `{name}` has invalid scopes: {err}"))?;
+    let expires = match token_file.expires {
+        Some(expires) => Some(
+            DateTime::parse_from_rfc3339(&expires.to_string())
+                .map_err(|err| {
+                    anyhow!("`tokens.{name}.expires` in secrets/admin.toml needs a date, time and offset: {err}")
+                })?
+                .to_utc(),
+        ),
+        None => None,
+    };
+
+    Ok(AdminToken {
+        name,
+        hash,
+        scopes,
+        expires,
     })
 }
 
-fn try_read_secrets_keys() -> anyhow::Result<SecretsKeysFile> {
-    let mut keys_file = File::open("secrets/keys.toml")?;
-    let mut read_keys_file = String::new();
-    keys_file.read_to_string(&mut read_keys_file)?;
-    Ok(from_str(&read_keys_file)?)
+/// Admin tokens are stored as sha256 hash of the token.
+pub fn hash_admin_token(token: &str) -> [u8; 32] {
+    Sha256::digest(token.trim().as_bytes()).into()
+}
+
+/// Name of the environment variable the secret is looked up in.
+fn secret_env_name(name: &str) -> String {
+    format!(
+        "MYCOLOG_SECRET_{}",
+        name.to_uppercase()
+            .replace(|c: char| !c.is_ascii_alphanumeric(), "_")
+    )
 }
 
-fn try_read_secrets_db() -> anyhow::Result<SecretsDbFile> {
-    let mut db_file = File::open("secrets/db.toml")?;
-    let mut read_db_file = String::new();
-    db_file.read_to_string(&mut read_db_file)?;
-    Ok(from_str(&read_db_file)?)
+fn missing_secret_error(name: &str, key: &str, file: &str) -> anyhow::Error {
+    anyhow!(
+        "secret `{name}` is missing, set `{key}` in secrets/{file}.toml, the `{}` environment variable or the `{name}` credential",
+        secret_env_name(name)
+    )
 }
 
-fn try_read_secrets_admin() -> anyhow::Result<SecretsAdminFile> {
-    let mut admin_file = File::open("secrets/admin.toml")?;
-    let mut read_admin_file = String::new();
-    admin_file.read_to_string(&mut read_admin_file)?;
-    Ok(from_str(&read_admin_file)?)
+/// All secrets files are optional, so a missing file is not an error.
+fn try_read_secrets_file<T: DeserializeOwned + Default>(name: &str) -> anyhow::Result<T> {
+    let mut secrets_file = match File::open(format!("secrets/{name}.toml")) {
+        Ok(file) => file,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(T::default()),
+        Err(err) => bail!(err),
+    };
+    let mut read_secrets_file = String::new();
+    secrets_file.read_to_string(&mut read_secrets_file)?;
+    from_str(&read_secrets_file).map_err(|err| anyhow!("secrets/{name}.toml is invalid: {err}"))
 }
 
 #[derive(Clone, Debug)]
@@ -91,21 +254,33 @@ pub struct MycologSecrets {
     pub keys: SecretsKeys,
     pub db: SecretsDb,
     pub admin: SecretsAdmin,
+    pub backup: SecretsBackup,
+}
+
+impl MycologSecrets {
+    /// Replaces the rotatable secrets, i.e. the admin tokens and the webhook signing key, with
+    /// the ones of `reloaded`. All clones observe the new values.
+    pub fn rotate(&self, reloaded: &MycologSecrets) {
+        *self.admin.tokens.write().unwrap() = reloaded.admin.tokens();
+        *self.keys.mailersend_webhook.write().unwrap() = reloaded.keys.mailersend_webhook();
+    }
 }
 
 #[derive(Clone)]
 pub struct SecretsKeys {
-    mailersend_api: String,
-    mailersend_webhook: String,
+    mailersend_api: Option<String>,
+    mailersend_webhook: Arc<RwLock<Option<String>>>,
 }
 
 impl SecretsKeys {
-    pub fn mailersend_api(&self) -> String {
+    /// Key for the mailersend api, sending emails is disabled if [None].
+    pub fn mailersend_api(&self) -> Option<String> {
         self.mailersend_api.clone()
     }
 
-    pub fn mailersend_webhook(&self) -> String {
-        self.mailersend_webhook.clone()
+    /// Key for verifying webhook signatures, the webhook is disabled if [None].
+    pub fn mailersend_webhook(&self) -> Option<String> {
+        self.mailersend_webhook.read().unwrap().clone()
     }
 }
 
@@ -145,39 +320,284 @@ impl Debug for SecretsDb {
 
 #[derive(Clone)]
 pub struct SecretsAdmin {
-    token: String,
+    tokens: Arc<RwLock<Vec<AdminToken>>>,
 }
 
 impl SecretsAdmin {
-    pub fn token(&self) -> String {
-        self.token.clone()
+    /// The admin api is disabled if empty.
+    pub fn tokens(&self) -> Vec<AdminToken> {
+        self.tokens.read().unwrap().clone()
+    }
+
+    /// The configured token matching `token`. Hashes are compared in constant time and every
+    /// token is checked, so the timing does not reveal how much of a token matched.
+    pub fn authenticate(&self, token: &str) -> Option<AdminToken> {
+        let hash = hash_admin_token(token);
+        let mut matched = None;
+        for admin_token in self.tokens.read().unwrap().iter() {
+            if bool::from(admin_token.hash.ct_eq(&hash)) {
+                matched = Some(admin_token.clone());
+            }
+        }
+        matched
     }
 }
 
 impl Debug for SecretsAdmin {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("SecretsAdmin").field("token", &"?").finish()
+        f.debug_struct("SecretsAdmin")
+            .field("tokens", &self.tokens.read().unwrap())
+            .finish()
+    }
+}
+
+#[derive(Clone)]
+pub struct AdminToken {
+    pub name: String,
+    hash: [u8; 32],
+    pub scopes: BTreeSet<AdminScope>,
+    pub expires: Option<DateTime<Utc>>,
+}
+
+impl AdminToken {
+    pub fn has_scope(&self, scope: AdminScope) -> bool {
+        self.scopes.contains(&scope)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires.is_some_and(|expires| expires <= Utc::now())
+    }
+}
+
+impl Debug for AdminToken {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AdminToken")
+            .field("name", &self.name)
+            .field("hash", &"?")
+            .field("scopes", &self.scopes)
+            .field("expires", &self.expires)
+            .finish()
+    }
+}
+
+/// Permissions granted to an admin token.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AdminScope {
+    /// Download backups and read their verifications.
+    BackupRead,
+    /// Restore uploaded backups.
+    Restore,
+    /// Query the database with root access.
+    QueryRoot,
+    /// List, lock and unlock users.
+    UserManage,
+    /// Manage jobs, migrations and schedules.
+    Maintenance,
+}
+
+impl AdminScope {
+    pub const ALL: [AdminScope; 5] = [
+        AdminScope::BackupRead,
+        AdminScope::Restore,
+        AdminScope::QueryRoot,
+        AdminScope::UserManage,
+        AdminScope::Maintenance,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AdminScope::BackupRead => "backup-read",
+            AdminScope::Restore => "restore",
+            AdminScope::QueryRoot => "query-root",
+            AdminScope::UserManage => "user-manage",
+            AdminScope::Maintenance => "maintenance",
+        }
+    }
+}
+
+impl FromStr for AdminScope {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        AdminScope::ALL
+            .into_iter()
+            .find(|scope| scope.as_str() == s)
+            .ok_or(anyhow!("unknown admin scope `{s}`"))
+    }
+}
+
+#[derive(Clone)]
+pub struct SecretsBackup {
+    key: Option<[u8; 32]>,
+    targets: BTreeMap<String, BackupTargetCredentials>,
+}
+
+impl SecretsBackup {
+    /// Key for encrypting backups, backups are written unencrypted if [None].
+    pub fn key(&self) -> Option<[u8; 32]> {
+        self.key
+    }
+
+    /// Credentials of the backup target with the given name.
+    pub fn target_credentials(&self, name: &str) -> Option<BackupTargetCredentials> {
+        self.targets.get(name).cloned()
+    }
+}
+
+impl Debug for SecretsBackup {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SecretsBackup")
+            .field("key", &self.key.map(|_| "?"))
+            .field("targets", &self.targets.keys())
+            .finish()
+    }
+}
+
+#[derive(Clone)]
+pub struct BackupTargetCredentials {
+    access_key: String,
+    secret_key: String,
+}
+
+impl BackupTargetCredentials {
+    pub fn access_key(&self) -> String {
+        self.access_key.clone()
+    }
+
+    pub fn secret_key(&self) -> String {
+        self.secret_key.clone()
+    }
+}
+
+impl Debug for BackupTargetCredentials {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BackupTargetCredentials")
+            .field("access_key", &self.access_key)
+            .field("secret_key", &"?")
+            .finish()
     }
 }
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Default, Serialize, Deserialize)]
 struct SecretsKeysFile {
     mailersend: Option<KeysFileMailersend>,
 }
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Default, Serialize, Deserialize)]
 struct KeysFileMailersend {
     api_key: Option<String>,
     webhook_signature: Option<String>,
 }
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Default, Serialize, Deserialize)]
 struct SecretsDbFile {
     user: Option<String>,
     password: Option<String>,
 }
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Default, Serialize, Deserialize)]
 struct SecretsAdminFile {
+    /// Deprecated single token with all scopes.
     token: Option<String>,
+    tokens: Option<BTreeMap<String, AdminTokenFile>>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct AdminTokenFile {
+    /// Hex encoded sha256 hash of the token.
+    hash: Option<String>,
+    scopes: Option<Vec<String>>,
+    expires: Option<toml::value::Datetime>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct SecretsBackupFile {
+    key: Option<String>,
+    targets: Option<BTreeMap<String, BackupFileTarget>>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct BackupFileTarget {
+    access_key: Option<String>,
+    secret_key: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    fn admin_token(name: &str, token: &str, expires: Option<DateTime<Utc>>) -> AdminToken {
+        AdminToken {
+            name: name.to_string(),
+            hash: hash_admin_token(token),
+            scopes: BTreeSet::from([AdminScope::BackupRead]),
+            expires,
+        }
+    }
+
+    fn secrets_admin(tokens: Vec<AdminToken>) -> SecretsAdmin {
+        SecretsAdmin {
+            tokens: Arc::new(RwLock::new(tokens)),
+        }
+    }
+
+    #[test]
+    fn authenticates_matching_token() {
+        let admin = secrets_admin(vec![
+            admin_token("backup", "first-secret", None),
+            admin_token("restore", "second-secret", None),
+        ]);
+
+        let token = admin.authenticate("second-secret").unwrap();
+        assert_eq!(token.name, "restore");
+        assert!(token.has_scope(AdminScope::BackupRead));
+        assert!(!token.has_scope(AdminScope::QueryRoot));
+    }
+
+    #[test]
+    fn rejects_unknown_token() {
+        let admin = secrets_admin(vec![admin_token("backup", "first-secret", None)]);
+
+        assert!(admin.authenticate("first-secre").is_none());
+        assert!(admin.authenticate("").is_none());
+        assert!(secrets_admin(Vec::new()).authenticate("").is_none());
+    }
+
+    #[test]
+    fn expiry_is_left_to_the_caller() {
+        let admin = secrets_admin(vec![
+            admin_token("old", "old-secret", Some(Utc::now() - Duration::days(1))),
+            admin_token("new", "new-secret", Some(Utc::now() + Duration::days(1))),
+        ]);
+
+        assert!(admin.authenticate("old-secret").unwrap().is_expired());
+        assert!(!admin.authenticate("new-secret").unwrap().is_expired());
+    }
+
+    #[test]
+    fn parses_scopes() {
+        for scope in AdminScope::ALL {
+            assert_eq!(AdminScope::from_str(scope.as_str()).unwrap(), scope);
+        }
+        assert_eq!(
+            AdminScope::from_str("user-manage").unwrap(),
+            AdminScope::UserManage
+        );
+        assert!(AdminScope::from_str("user_manage").is_err());
+    }
+
+    #[test]
+    fn missing_secret_names_sanitized_env_var() {
+        let err = missing_secret_error("admin_token_ci-deploy_hash", "hash", "admin");
+        assert_eq!(
+            secret_env_name("admin_token_ci-deploy_hash"),
+            "MYCOLOG_SECRET_ADMIN_TOKEN_CI_DEPLOY_HASH"
+        );
+        assert!(err
+            .to_string()
+            .contains("`MYCOLOG_SECRET_ADMIN_TOKEN_CI_DEPLOY_HASH` environment variable"));
+    }
 }
@@ -3,28 +3,30 @@ use std::path::{Path, PathBuf};
 
 use tracing::{info_span, warn};
 
-pub fn prepare_application_dirs() -> anyhow::Result<()> {
-    create_application_dirs()?;
-    check_application_dirs()?;
+use crate::config::MycologConfig;
+
+pub fn prepare_application_dirs(config: &MycologConfig) -> anyhow::Result<()> {
+    create_application_dirs(config)?;
+    check_application_dirs(config)?;
 
     Ok(())
 }
 
-fn create_application_dirs() -> anyhow::Result<()> {
-    create_dir_all("logs/")?;
-    create_dir_all("data/")?;
-    create_dir_all("images/")?;
-    create_dir_all("backups/")?;
+fn create_application_dirs(config: &MycologConfig) -> anyhow::Result<()> {
+    create_dir_all(&config.logs_dir)?;
+    create_dir_all(&config.data_dir)?;
+    create_dir_all(&config.images_dir)?;
+    create_dir_all(&config.backups_dir)?;
     Ok(())
 }
 
-fn check_application_dirs() -> anyhow::Result<()> {
-    check_dir_all("migrations/")?;
-    check_dir_all("schedules/")?;
-    check_dir_all("site/")?;
+fn check_application_dirs(config: &MycologConfig) -> anyhow::Result<()> {
+    check_dir_all(&config.migrations_dir)?;
+    check_dir_all(&config.schedules_dir)?;
+    check_dir_all(&config.site_dir)?;
     check_dir_all("secrets/")?;
     check_dir_all("config/")?;
-    check_dir_all("emails/")?;
+    check_dir_all(&config.emails_dir)?;
     Ok(())
 }
 
@@ -3,13 +3,14 @@ use std::process::exit;
 use std::sync::{Arc, Mutex};
 
 use anyhow::anyhow;
+use tokio::sync::watch;
 use tokio::sync::Mutex as AsyncMutex;
 use tracing::{debug, error, info, instrument};
 use tracing_subscriber::util::SubscriberInitExt;
 
 use crate::application::{
-    create_database_system, create_email_manager, create_image_manager, load_schedule_queries,
-    EmailManager,
+    create_database_system, create_email_manager, create_image_manager, create_job_queue,
+    create_job_registry, load_schedule_queries, EmailManager,
 };
 use crate::cli::MycologArguments;
 use crate::config::parse_config;
@@ -20,13 +21,12 @@ use crate::startup::directories::prepare_application_dirs;
 use crate::startup::logging::{setup_logging, LoggingHandle};
 use crate::utils::asynchronous::run_catch;
 
-mod directories;
+pub mod directories;
 pub mod logging;
 
 #[instrument]
 pub async fn startup(arguments: MycologArguments) -> Arc<MycologContext> {
     info!("Starting up application...");
-    // - Check for database import
     // - Sync images with database content (remove unregistered, remove invalid db entries)
     // - Start backup task
 
@@ -43,8 +43,7 @@ pub async fn startup(arguments: MycologArguments) -> Arc<MycologContext> {
 }
 
 async fn try_startup(arguments: MycologArguments) -> anyhow::Result<MycologContext> {
-    let logging = setup_logging()?;
-    prepare_application_dirs()?;
+    let logging = setup_logging(&arguments)?;
 
     build_context(arguments, logging).await
 }
@@ -55,11 +54,14 @@ async fn build_context(
     logging: LoggingHandle,
 ) -> anyhow::Result<MycologContext> {
     let config = parse_config(arguments);
-    let secrets = parse_secrets();
+    prepare_application_dirs(&config)?;
+    let secrets = parse_secrets(&config);
     let db = create_database_system(&config, &secrets).await?;
     let email = create_email_manager(&config, &secrets, &db).await?;
     let images = create_image_manager(&config, &secrets, &db).await?;
-    let schedules = load_schedule_queries("schedules/").await?;
+    let (schedules, _) = watch::channel(load_schedule_queries(&config.schedules_dir).await?);
+    let jobs = create_job_registry()?;
+    let queue = create_job_queue(&config, &db);
 
     let exit_receiver =
         AsyncMutex::new(take_exit_recevier().ok_or(anyhow!("exit receiver was already in use"))?);
@@ -72,7 +74,10 @@ async fn build_context(
         email,
         images,
         schedules,
+        jobs,
+        queue,
         logging,
+        admin_token_uses: Default::default(),
         tasks: Default::default(),
         task_cancel_token: Default::default(),
     })
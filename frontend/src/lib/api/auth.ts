@@ -87,4 +87,47 @@ export async function logout(): Promise<ResponseResult<string, string>> {
         status: response.status,
         error: await response.text()
     }
-}
\ No newline at end of file
+}
+export async function requestPasswordReset(email: string): Promise<ResponseResult<string, string>> {
+    const response = await fetchBackend("/auth/reset/request", {
+        method: "POST",
+        headers: {
+            "Content-Type": "application/json"
+        },
+        body: JSON.stringify({
+            email
+        })
+    })
+
+    return response.ok ? {
+        status: response.status,
+        response: await response.text(),
+    } : {
+        status: response.status,
+        error: await response.text()
+    }
+}
+
+export async function confirmPasswordReset(
+    token: string,
+    password: string
+): Promise<ResponseResult<string, string>> {
+    const response = await fetchBackend("/auth/reset/confirm", {
+        method: "POST",
+        headers: {
+            "Content-Type": "application/json"
+        },
+        body: JSON.stringify({
+            token,
+            password
+        })
+    })
+
+    return response.ok ? {
+        status: response.status,
+        response: await response.text(),
+    } : {
+        status: response.status,
+        error: await response.text()
+    }
+}
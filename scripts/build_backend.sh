@@ -4,4 +4,4 @@
 scriptdir="$(dirname "$0")"
 cd "$scriptdir" && cd ../ || exit;
 
-cargo +nightly -Z unstable-options build --manifest-path backend/Cargo.toml --bin mycolog --release --features prod-env;
+cargo +nightly -Z unstable-options build --manifest-path backend/Cargo.toml --bin mycolog --release;